@@ -0,0 +1,339 @@
+use serenity::builder::{CreateEmbed, CreateMessage};
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::{Args, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use std::cmp;
+
+use crate::commands::giveme::{MAX_RATING, MIN_RATING};
+use crate::commands::veto::DEFAULT_MAX_REROLLS;
+use crate::core::data::*;
+use crate::core::pool::ProblemPool;
+use crate::error_response;
+use crate::utils::message_creator::*;
+use crate::utils::time::*;
+
+const DEFAULT_POTD_MIN_RATING: u32 = 1200;
+const DEFAULT_POTD_MAX_RATING: u32 = 1800;
+
+async fn show_help() -> CreateMessage {
+  let embed = CreateEmbed::new()
+    .title("Usage of `config`")
+    .description(
+      "`~config` (show the configuration of the server)\n
+      `~config pool <div1 div2 div3 div4 edu global goodbye kotlin other / all> [ids:<from>-<to>] [year:<year>]` (contests problems are picked from)\n
+      `~config pool reset`\n
+      `~config potd <#channel> <HH:MM> [min rating] [max rating]` (post a problem of the day)\n
+      `~config potd off`\n
+      `~config timezone <+7 / -05:30 / UTC+9>` (used for the problem of the day and streaks)\n
+      `~config contests <#channel>` (remind the server of upcoming codeforces rounds)\n
+      `~config contests off`\n
+      `~config ratings <#channel>` (post the rating changes of the members after a round)\n
+      `~config ratings off`\n
+      `~config moderators <#channel>` (report suspicious solves of the matches privately)\n
+      `~config moderators off`\n
+      `~config rerolls <number>` (problems that can be vetoed or rerolled before a match, 0 turns it off)\n
+      `~config spectators <hidden / shown>` (whether `~match watch` shows the problems before the match ends)",
+    )
+    .color(Colour::DARK_GREEN);
+  CreateMessage::new().embed(embed)
+}
+
+fn create_config_message(config: &GuildConfig) -> CreateMessage {
+  let embed = CreateEmbed::new()
+    .title("Server configuration")
+    .field(
+      "Problem pool",
+      config.pool.clone().unwrap_or_default().describe(),
+      false,
+    )
+    .field(
+      "Problem of the day",
+      match &config.potd {
+        Some(potd) => format!(
+          "<#{channel}> at {hours:0>2}:{minutes:0>2} {timezone}, rating {min} to {max}",
+          channel = potd.channel_id,
+          hours = potd.time / 60,
+          minutes = potd.time % 60,
+          timezone = format_utc_offset(config.utc_offset),
+          min = potd.min_rating,
+          max = potd.max_rating
+        ),
+        None => "Off".to_owned(),
+      },
+      false,
+    )
+    .field("Timezone", format_utc_offset(config.utc_offset), false)
+    .field(
+      "Contest reminders",
+      match config.contest_channel {
+        Some(channel_id) => format!("<#{channel_id}>"),
+        None => "Off".to_owned(),
+      },
+      false,
+    )
+    .field(
+      "Rating changes",
+      match config.rating_channel {
+        Some(channel_id) => format!("<#{channel_id}>"),
+        None => "Off".to_owned(),
+      },
+      false,
+    )
+    .field(
+      "Suspicious solve reports",
+      match config.moderator_channel {
+        Some(channel_id) => format!("<#{channel_id}>"),
+        None => "Off".to_owned(),
+      },
+      false,
+    )
+    .field(
+      "Rerolls per match",
+      config
+        .max_rerolls
+        .unwrap_or(DEFAULT_MAX_REROLLS)
+        .to_string(),
+      false,
+    )
+    .field(
+      "Problems for spectators",
+      if config.hide_spectator_problems {
+        "Hidden until the match ends"
+      } else {
+        "Shown"
+      },
+      false,
+    )
+    .colour(Colour::TEAL)
+    .timestamp(Timestamp::now());
+  CreateMessage::new().embed(embed)
+}
+
+pub fn extract_channel_id(mention: &str) -> Option<ChannelId> {
+  mention
+    .trim()
+    .strip_prefix("<#")?
+    .strip_suffix('>')?
+    .parse::<u64>()
+    .ok()
+    .map(ChannelId::new)
+}
+
+// `HH:MM` into minutes after midnight
+pub fn parse_time_of_day(time: &str) -> Option<u32> {
+  let (hours, minutes) = time.split_once(':')?;
+  let (hours, minutes) = (hours.parse::<u32>().ok()?, minutes.parse::<u32>().ok()?);
+  if hours >= 24 || minutes >= 60 {
+    return None;
+  }
+  Some(hours * 60 + minutes)
+}
+
+fn parse_potd_config(mut args: Args) -> Result<Option<PotdConfig>, String> {
+  let channel = args
+    .single::<String>()
+    .map_err(|_| "Please provide a channel for the problem of the day".to_owned())?;
+  if channel == "off" {
+    return Ok(None);
+  }
+  let channel_id =
+    extract_channel_id(&channel).ok_or(format!("`{channel}` is not a channel mention"))?;
+  let time = args
+    .single::<String>()
+    .ok()
+    .and_then(|time| parse_time_of_day(&time))
+    .ok_or("Please provide the time of the day as `HH:MM`".to_owned())?;
+  let min_rating = args.single::<u32>().unwrap_or(DEFAULT_POTD_MIN_RATING);
+  let max_rating = args
+    .single::<u32>()
+    .unwrap_or(cmp::max(min_rating, DEFAULT_POTD_MAX_RATING));
+  if min_rating > max_rating || min_rating < MIN_RATING || max_rating > MAX_RATING {
+    return Err(format!(
+      "Please provide a rating band between {MIN_RATING} and {MAX_RATING}"
+    ));
+  }
+  Ok(Some(PotdConfig {
+    channel_id,
+    time,
+    min_rating,
+    max_rating,
+  }))
+}
+
+// `<#channel>` or `off`
+fn parse_channel_option(mut args: Args, purpose: &str) -> Result<Option<ChannelId>, String> {
+  let channel = args
+    .single::<String>()
+    .map_err(|_| format!("Please provide a channel for {purpose}"))?;
+  if channel == "off" {
+    return Ok(None);
+  }
+  extract_channel_id(&channel)
+    .map(Some)
+    .ok_or(format!("`{channel}` is not a channel mention"))
+}
+
+// whether the author may change the configuration of the server
+fn can_manage_guild(ctx: &Context, msg: &Message) -> bool {
+  msg
+    .author_permissions(ctx)
+    .is_some_and(|permissions| permissions.manage_guild())
+}
+
+#[command]
+#[only_in(guilds)]
+pub async fn config(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+  let guild_id = match msg.guild_id {
+    Some(id) => id,
+    None => return Ok(()),
+  };
+  let option = match args.single::<String>() {
+    Ok(option) => option,
+    Err(_) => {
+      let config = get_guild_config(ctx, Some(guild_id)).await;
+      msg
+        .channel_id
+        .send_message(&ctx.http, create_config_message(&config))
+        .await?;
+      return Ok(());
+    }
+  };
+
+  // everyone can read the configuration, changing it needs the permission to manage the server
+  let changes = !matches!(option.as_str(), "help" | "h");
+  if changes && !can_manage_guild(ctx, msg) {
+    error_response!(
+      ctx,
+      msg,
+      "You need the Manage Server permission to change the configuration".to_owned()
+    );
+    return Ok(());
+  }
+
+  match option.as_str() {
+    "pool" => {
+      let pool = match args.rest() {
+        "" => {
+          error_response!(
+            ctx,
+            msg,
+            "Please provide the contests of the pool".to_owned()
+          );
+          return Ok(());
+        }
+        "reset" => None,
+        spec => match ProblemPool::parse(spec) {
+          Ok(pool) => Some(pool),
+          Err(why) => {
+            error_response!(ctx, msg, why);
+            return Ok(());
+          }
+        },
+      };
+      edit_guild_config(ctx, guild_id, |config| config.pool = pool).await;
+    }
+    "potd" => match parse_potd_config(args) {
+      Ok(potd) => edit_guild_config(ctx, guild_id, |config| config.potd = potd).await,
+      Err(why) => {
+        error_response!(ctx, msg, why);
+        return Ok(());
+      }
+    },
+    "timezone" | "tz" => match parse_utc_offset(args.rest()) {
+      Some(offset) => edit_guild_config(ctx, guild_id, |config| config.utc_offset = offset).await,
+      None => {
+        error_response!(
+          ctx,
+          msg,
+          "Please provide an offset from UTC like `+7`, `-05:30` or `UTC+9`".to_owned()
+        );
+        return Ok(());
+      }
+    },
+    "contests" => match parse_channel_option(args, "the contest reminders") {
+      Ok(channel_id) => {
+        edit_guild_config(ctx, guild_id, |config| config.contest_channel = channel_id).await
+      }
+      Err(why) => {
+        error_response!(ctx, msg, why);
+        return Ok(());
+      }
+    },
+    "ratings" => match parse_channel_option(args, "the rating changes") {
+      Ok(channel_id) => {
+        edit_guild_config(ctx, guild_id, |config| config.rating_channel = channel_id).await
+      }
+      Err(why) => {
+        error_response!(ctx, msg, why);
+        return Ok(());
+      }
+    },
+    "moderators" | "mods" => match parse_channel_option(args, "the suspicious solve reports") {
+      Ok(channel_id) => {
+        edit_guild_config(ctx, guild_id, |config| {
+          config.moderator_channel = channel_id
+        })
+        .await
+      }
+      Err(why) => {
+        error_response!(ctx, msg, why);
+        return Ok(());
+      }
+    },
+    "rerolls" => match args.single::<u32>() {
+      Ok(rerolls) => {
+        edit_guild_config(ctx, guild_id, |config| config.max_rerolls = Some(rerolls)).await
+      }
+      Err(_) => {
+        error_response!(
+          ctx,
+          msg,
+          "Please provide how many problems can be rerolled".to_owned()
+        );
+        return Ok(());
+      }
+    },
+    "spectators" => match args.single::<String>().as_deref() {
+      Ok("hidden") => {
+        edit_guild_config(ctx, guild_id, |config| {
+          config.hide_spectator_problems = true
+        })
+        .await
+      }
+      Ok("shown") => {
+        edit_guild_config(ctx, guild_id, |config| {
+          config.hide_spectator_problems = false
+        })
+        .await
+      }
+      _ => {
+        error_response!(ctx, msg, "Please choose `hidden` or `shown`".to_owned());
+        return Ok(());
+      }
+    },
+    "help" | "h" => {
+      msg
+        .channel_id
+        .send_message(&ctx.http, show_help().await)
+        .await?;
+      return Ok(());
+    }
+    _ => {
+      error_response!(
+        ctx,
+        msg,
+        format!("Unknown option `{option}`, try `~config help`")
+      );
+      return Ok(());
+    }
+  }
+
+  let config = get_guild_config(ctx, Some(guild_id)).await;
+  msg
+    .channel_id
+    .send_message(&ctx.http, create_config_message(&config))
+    .await?;
+  Ok(())
+}
@@ -0,0 +1,308 @@
+use std::collections::BTreeSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serenity::builder::{CreateEmbed, CreateEmbedFooter, CreateMessage};
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::{Args, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use tokio::time::{sleep, Duration};
+use tracing::{error, info};
+
+use crate::core::data::*;
+use crate::error_response;
+use crate::utils::message_creator::*;
+use crate::utils::time::*;
+
+const CONTEST_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 5);
+// reminders are posted this many seconds before a round starts
+const REMINDER_LEADS: [u64; 2] = [60 * 60 * 24, 60 * 60];
+const SUBSCRIBE_EMOJI: char = '🔔';
+const CONTESTS_SHOWN: usize = 10;
+
+static CONTEST_SCHEDULER_STARTED: AtomicBool = AtomicBool::new(false);
+
+async fn show_help() -> CreateMessage {
+  let embed = CreateEmbed::new()
+    .title("Usage of `contests`")
+    .description(
+      "`~contests [upcoming (default) / running]` (codeforces rounds with their start time)\n
+      An admin can post reminders before every round with `~config contests <#channel>`, react with :bell: on a reminder to get a DM too",
+    )
+    .color(Colour::DARK_GREEN);
+  CreateMessage::new().embed(embed)
+}
+
+fn contest_url(contest: &Contest) -> String {
+  format!("https://codeforces.com/contest/{id}", id = contest.id)
+}
+
+// rounds that haven't started yet, the closest first
+pub fn get_upcoming_contests(contests: &[Contest]) -> Vec<Contest> {
+  let mut upcoming = contests
+    .iter()
+    .filter(|contest| contest.phase == "BEFORE" && contest.startTimeSeconds.is_some())
+    .cloned()
+    .collect::<Vec<_>>();
+  upcoming.sort_by_key(|contest| contest.startTimeSeconds);
+  upcoming
+}
+
+pub fn get_running_contests(contests: &[Contest]) -> Vec<Contest> {
+  let mut running = contests
+    .iter()
+    .filter(|contest| contest.phase == "CODING" && contest.startTimeSeconds.is_some())
+    .cloned()
+    .collect::<Vec<_>>();
+  running.sort_by_key(|contest| contest.startTimeSeconds);
+  running
+}
+
+fn get_contests_string(contests: &[Contest], running: bool) -> String {
+  if contests.is_empty() {
+    return if running {
+      "No round is running right now".to_owned()
+    } else {
+      "No round is scheduled yet".to_owned()
+    };
+  }
+  contests
+    .iter()
+    .take(CONTESTS_SHOWN)
+    .map(|contest| {
+      let start = contest.startTimeSeconds.unwrap_or_default();
+      let link = create_href(contest_url(contest), &contest.name);
+      if running {
+        format!(
+          "{link}\nEnds <t:{end}:R>",
+          end = start + contest.durationSeconds
+        )
+      } else {
+        format!(
+          "{link}\n<t:{start}:F> (<t:{start}:R>), {length}",
+          length = format_duration(contest.durationSeconds)
+        )
+      }
+    })
+    .collect::<Vec<_>>()
+    .join("\n\n")
+}
+
+fn create_reminder_message(contest: &Contest, start: u64) -> CreateMessage {
+  let embed = CreateEmbed::new()
+    .title(contest.name.clone())
+    .url(contest_url(contest))
+    .description(format!("Starts <t:{start}:F> (<t:{start}:R>)"))
+    .field("Length", format_duration(contest.durationSeconds), true)
+    .colour(Colour::BLUE)
+    .footer(CreateEmbedFooter::new(format!(
+      "React with {SUBSCRIBE_EMOJI} to get a DM before it starts"
+    )));
+  CreateMessage::new().embed(embed)
+}
+
+// members who reacted to any of the reminders, without the bot itself
+async fn get_subscribers(ctx: &Context, reminders: &[ContestReminder]) -> Vec<UserId> {
+  let mut subscribers = BTreeSet::new();
+  for reminder in reminders.iter() {
+    let users = reminder
+      .channel_id
+      .reaction_users(
+        &ctx.http,
+        reminder.message_id,
+        SUBSCRIBE_EMOJI,
+        Some(100),
+        None,
+      )
+      .await
+      .unwrap_or_default();
+    subscribers.extend(users.iter().filter(|user| !user.bot).map(|user| user.id));
+  }
+  subscribers.into_iter().collect()
+}
+
+async fn notify_subscribers(
+  ctx: &Context,
+  reminders: &[ContestReminder],
+  name: &String,
+  start: u64,
+) {
+  for user_id in get_subscribers(ctx, reminders).await {
+    let embed = CreateEmbed::new()
+      .description(format!("**{name}** starts <t:{start}:R>"))
+      .colour(Colour::BLUE);
+    if let Err(why) = user_id
+      .direct_message(&ctx.http, CreateMessage::new().embed(embed))
+      .await
+    {
+      error!("Can't send the contest reminder: {:?}", why);
+    }
+  }
+}
+
+async fn remind_guild(ctx: &Context, config: &GuildConfig, upcoming: &[Contest], now: u64) {
+  let channel_id = match config.contest_channel {
+    Some(channel_id) => channel_id,
+    None => return,
+  };
+  let reminders = match get_data(ctx).await {
+    Ok(data) => data.contest_reminders,
+    Err(_) => return,
+  };
+  for contest in upcoming.iter() {
+    let start = contest.startTimeSeconds.unwrap_or_default();
+    // only the closest reminder is posted when the bot was offline for the earlier ones
+    let lead = match REMINDER_LEADS
+      .iter()
+      .filter(|lead| start <= now + **lead)
+      .min()
+    {
+      Some(lead) => *lead,
+      None => continue,
+    };
+    let posted = reminders
+      .iter()
+      .filter(|reminder| reminder.guild_id == config.guild_id && reminder.contest_id == contest.id)
+      .cloned()
+      .collect::<Vec<_>>();
+    if posted.iter().any(|reminder| reminder.lead <= lead) {
+      continue;
+    }
+
+    info!(
+      "Reminding guild {} of contest {}",
+      config.guild_id, contest.id
+    );
+    let message = match channel_id
+      .send_message(&ctx.http, create_reminder_message(contest, start))
+      .await
+    {
+      Ok(message) => message,
+      Err(why) => {
+        error!("Can't post the contest reminder: {:?}", why);
+        continue;
+      }
+    };
+    let _ = message.react(&ctx.http, SUBSCRIBE_EMOJI).await;
+    notify_subscribers(ctx, &posted, &contest.name, start).await;
+    let reminder = ContestReminder {
+      guild_id: config.guild_id.clone(),
+      contest_id: contest.id,
+      start_time: start,
+      lead,
+      channel_id,
+      message_id: message.id,
+    };
+    edit_data(ctx, |data| data.contest_reminders.push(reminder)).await;
+  }
+}
+
+// DM the subscribers of rounds that just started and forget their reminders
+async fn close_started_contests(ctx: &Context, contests: &[Contest], now: u64) {
+  let started = match get_data(ctx).await {
+    Ok(data) => data
+      .contest_reminders
+      .into_iter()
+      .filter(|reminder| reminder.start_time <= now)
+      .collect::<Vec<_>>(),
+    Err(_) => return,
+  };
+  if started.is_empty() {
+    return;
+  }
+  let contest_ids = started
+    .iter()
+    .map(|reminder| reminder.contest_id)
+    .collect::<BTreeSet<_>>();
+  for contest_id in contest_ids {
+    let reminders = started
+      .iter()
+      .filter(|reminder| reminder.contest_id == contest_id)
+      .cloned()
+      .collect::<Vec<_>>();
+    let name = contests
+      .iter()
+      .find(|contest| contest.id == contest_id)
+      .map(|contest| contest.name.clone())
+      .unwrap_or(format!("Contest {contest_id}"));
+    notify_subscribers(ctx, &reminders, &name, reminders[0].start_time).await;
+  }
+  edit_data(ctx, |data| {
+    data
+      .contest_reminders
+      .retain(|reminder| reminder.start_time > now)
+  })
+  .await;
+}
+
+pub async fn contest_reminder_interactor(ctx: &Context) {
+  if CONTEST_SCHEDULER_STARTED.swap(true, Ordering::SeqCst) {
+    return;
+  }
+  let ctx = ctx.clone();
+  tokio::spawn(async move {
+    loop {
+      let guilds = get_data(&ctx)
+        .await
+        .map(|data| data.guilds)
+        .unwrap_or_default();
+      if guilds.iter().any(|config| config.contest_channel.is_some()) {
+        if let Ok(contests) = get_contests(false).await {
+          let now = unix_now();
+          let upcoming = get_upcoming_contests(&contests);
+          for config in guilds.iter() {
+            remind_guild(&ctx, config, &upcoming, now).await;
+          }
+          close_started_contests(&ctx, &contests, now).await;
+        }
+      }
+      sleep(CONTEST_CHECK_INTERVAL).await;
+    }
+  });
+}
+
+#[command]
+pub async fn contests(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+  let running = match args.single::<String>().as_deref() {
+    Ok("upcoming") | Err(_) => false,
+    Ok("running") => true,
+    Ok("help") | Ok("h") => {
+      msg
+        .channel_id
+        .send_message(&ctx.http, show_help().await)
+        .await?;
+      return Ok(());
+    }
+    Ok(option) => {
+      error_response!(
+        ctx,
+        msg,
+        format!("Unknown option `{option}`, try `~contests help`")
+      );
+      return Ok(());
+    }
+  };
+  let contests = match get_contests(false).await {
+    Ok(contests) => contests,
+    Err(why) => {
+      error_response!(ctx, msg, why);
+      return Ok(());
+    }
+  };
+  let (title, contests) = if running {
+    ("Running rounds", get_running_contests(&contests))
+  } else {
+    ("Upcoming rounds", get_upcoming_contests(&contests))
+  };
+  let embed = CreateEmbed::new()
+    .title(title)
+    .description(get_contests_string(&contests, running))
+    .colour(Colour::BLUE)
+    .timestamp(Timestamp::now());
+  msg
+    .channel_id
+    .send_message(&ctx.http, CreateMessage::new().embed(embed))
+    .await?;
+  Ok(())
+}
@@ -1,33 +1,44 @@
 use crate::utils::message_creator::*;
+use crate::utils::time::unix_seconds;
 use crate::{create_duel, error_response, find_user_in_data, get_user_rating};
 
 use serenity::builder::{CreateEmbed, CreateMessage};
 use serenity::collector::MessageCollector;
 use serenity::framework::standard::macros::command;
-use serenity::framework::standard::{Args, CommandResult};
+use serenity::framework::standard::{Args, CommandResult, Delimiter};
 use serenity::futures::StreamExt;
 use serenity::model::prelude::*;
 use serenity::prelude::*;
 
+use std::time::SystemTime;
+
 use tokio::time::Duration;
 
 use crate::commands::giveme::*;
 use crate::commands::handle::*;
 use crate::commands::lockout::*;
+use crate::commands::match_control::*;
+use crate::commands::tournament::*;
+use crate::commands::veto::*;
 
+use crate::core::achievement::*;
+use crate::core::anticheat::*;
 use crate::core::data::User;
 use crate::core::data::*;
+use crate::core::pool::ProblemPool;
+use crate::core::scoring::*;
 
-const DUEL_DURATION: Duration = Duration::from_millis(1000 * 60 * 90);
 const WAIT_DURATION: Duration = Duration::from_millis(1000 * 30);
 pub const DEFAULT_RATING: i32 = 1000;
 
 async fn show_help() -> CreateMessage {
   let embed = CreateEmbed::new()
     .title(format!("Usage of `duel`"))
-    .description(format!("`~duel <@user> [rating (optional)] (you can try to invite more than 1 user but i won't guarantee it will work)`\n
-      `~match finish (to confirm that you have solved the problem and try to end the match)`\n
-      `~match giveup (give up like a loser)`"))
+    .description("`~duel <@user> [rating (optional)] [pool=div2,edu,... (optional)] [fair (optional, nobody attempted the problem or took part in its contest)] (invite as many users as you want, everyone is ranked by the time they solved the problem)`\n
+      `~match finish (to confirm that you have solved the problem, the duel goes on until everyone solved it or gave up)`\n
+      `~match giveup (give up like a loser)`\n
+      `~match draw` / `~match extend <minutes>` (ends the match as a draw or adds time once every player asked for it)\n
+      `~match pause` / `~match resume` (freeze the clock, e.g. while Codeforces is down)")
     .color(Colour::DARK_GREEN);
   let builder = CreateMessage::new().embed(embed);
   builder
@@ -54,10 +65,15 @@ pub async fn get_problem_for_users(
   rating: u32,
   problem_set: &Vec<Problem>,
   user_submissions: &Vec<Vec<Submission>>,
+  filter: &ProblemFilter,
 ) -> Option<Problem> {
-  let problems_wrap =
-    get_problems_with_given_problemset(rating, problem_set.clone(), user_submissions[0].clone())
-      .await;
+  let problems_wrap = get_problems_with_given_problemset(
+    rating,
+    problem_set.clone(),
+    user_submissions[0].clone(),
+    filter,
+  )
+  .await;
 
   let mut problems: Vec<Problem>;
   match problems_wrap {
@@ -74,6 +90,7 @@ pub async fn get_problem_for_users(
       rating,
       problem_set.clone(),
       user_submissions[i].clone(),
+      filter,
     )
     .await
     {
@@ -105,31 +122,37 @@ pub async fn get_problem_for_users(
   Some(get_problem_with_weights(problems))
 }
 
-async fn handle_duel(ctx: &Context, msg: &Message, users: Vec<User>, rating_range: u32) {
-  let problems_wrap = get_problemset().await;
-  if let Err(_) = problems_wrap {
-    error_response!(ctx, msg, format!("We can't provide a problem"));
-    return;
-  }
-  let contests_wrap = get_contests(false).await;
-  if let Err(_) = contests_wrap {
-    error_response!(ctx, msg, format!("Can't fetch contests data"));
+pub async fn handle_duel(
+  ctx: &Context,
+  msg: &Message,
+  users: Vec<User>,
+  rating_range: u32,
+  pool: &ProblemPool,
+  filter: &ProblemFilter,
+) {
+  let problems_wrap = get_pool_problemset(pool).await;
+  if let Err(why) = problems_wrap {
+    error_response!(ctx, msg, why);
     return;
   }
 
-  let mut problems = problems_wrap.unwrap();
-  problems = filter_problemset(problems, contests_wrap.unwrap());
+  let problems = problems_wrap.unwrap();
   let user_submissions = get_all_user_submissions(&users).await;
   let problem_wrap =
-    get_problem_for_users(&users, rating_range, &problems, &user_submissions).await;
+    get_problem_for_users(&users, rating_range, &problems, &user_submissions, filter).await;
   if problem_wrap == None {
     error_response!(ctx, msg, format!("We can't provide a problem"));
     return;
   }
 
-  let problem = problem_wrap.unwrap();
+  // the players are busy during the veto, the clock starts once it is over
+  let mut duel = create_duel(ctx, msg, users, &problem_wrap.unwrap()).await;
+  veto_problems(ctx, msg, &duel.players, &mut duel.problems, pool, filter).await;
+  duel.begin_time = SystemTime::now();
+  save_duel(ctx, &duel).await;
+
   let message = create_problem_message(
-    &problem,
+    &duel.problems[0],
     format!(
       "You guys will compete in 1 hour and 30 minutes to solve this problem.
     \nType `~finish` if you have solved the problem!"
@@ -138,19 +161,239 @@ async fn handle_duel(ctx: &Context, msg: &Message, users: Vec<User>, rating_rang
   )
   .unwrap();
   let _ = msg.channel_id.send_message(&ctx.http, message).await;
-
-  create_duel(ctx, msg, users, &problem).await;
-  let duel = get_duels(&ctx).await.unwrap().last().unwrap().clone();
   single_duel_interactor(&ctx, duel).await;
 }
 
-pub async fn single_duel_interactor(ctx: &Context, duel: Duel) {
-  let msg = duel.channel_id;
-  macro_rules! user_wins {
-    ($ctx: expr, $msg: expr, $user: expr) => {
+// free the players of the duel and pass its result on to the tournament it belongs to
+async fn finish_duel(ctx: &Context, duel: &Duel, winner: Option<String>) {
+  remove_duel(ctx, duel.players.clone()).await;
+  if let Some(tournament_id) = duel.tournament_id {
+    record_tournament_result(ctx, tournament_id, duel.duel_id, winner).await;
+  }
+}
+
+// the mean codeforces rating of the users rounded down to a hundred, unrated users are skipped
+pub async fn get_average_rating(users: &[User]) -> u32 {
+  let mut sum = 0;
+  let mut count = 0;
+  for user in users.iter() {
+    if let Ok(rating) = get_user_rating(&user.handle).await {
+      sum += rating;
+      count += 1;
+    }
+  }
+  if sum == 0 {
+    sum = DEFAULT_RATING as u32;
+    count = 1;
+  }
+  (sum / count) / 100 * 100
+}
+
+// the unix times the duel starts and ends at
+pub fn get_duel_window(duel: &Duel) -> (u64, u64) {
+  (
+    unix_seconds(duel.begin_time),
+    unix_seconds(duel.get_end_time()),
+  )
+}
+
+pub fn get_duel_attempt(duel: &Duel, index: usize) -> ProblemAttempt {
+  duel
+    .attempts
+    .as_ref()
+    .and_then(|attempts| attempts.get(index))
+    .and_then(|attempts| attempts.first())
+    .cloned()
+    .unwrap_or_default()
+}
+
+fn set_duel_attempt(duel: &mut Duel, index: usize, attempt: ProblemAttempt) {
+  let player_count = duel.players.len();
+  let attempts = duel
+    .attempts
+    .get_or_insert_with(|| vec![vec![ProblemAttempt::default()]; player_count]);
+  if let Some(player) = attempts.get_mut(index) {
+    *player = vec![attempt];
+  }
+}
+
+// the player neither solved the problem nor gave up yet
+pub fn is_playing(duel: &Duel, index: usize) -> bool {
+  get_duel_attempt(duel, index).solved_at.is_none()
+    && !duel.gave_up.contains(&duel.players[index].userId)
+}
+
+/*
+  The rank of every player: the ones who solved the problem by solve time, then the ones who didn't,
+  then the ones who gave up. Players at the same place share the rank
+*/
+pub fn get_duel_placements(duel: &Duel) -> Vec<(usize, usize)> {
+  let key = |index: usize| match get_duel_attempt(duel, index).solved_at {
+    Some(solved_at) => (0, solved_at),
+    None if duel.gave_up.contains(&duel.players[index].userId) => (2, 0),
+    None => (1, 0),
+  };
+  let mut order = (0..duel.players.len()).collect::<Vec<_>>();
+  order.sort_by_key(|index| key(*index));
+  let mut placements: Vec<(usize, usize)> = Vec::new();
+  for (pos, index) in order.iter().enumerate() {
+    let rank = match placements.last() {
+      Some((previous, rank)) if key(*previous) == key(*index) => *rank,
+      _ => pos + 1,
+    };
+    placements.push((*index, rank));
+  }
+  placements
+}
+
+// the only player at the first place, as long as they solved it or everyone else gave up
+fn get_duel_winner(duel: &Duel, placements: &[(usize, usize)]) -> Option<usize> {
+  match placements {
+    [(first, 1), rest @ ..] if rest.iter().all(|(_, rank)| *rank > 1) => {
+      let everyone_else_gave_up = rest
+        .iter()
+        .all(|(index, _)| duel.gave_up.contains(&duel.players[*index].userId));
+      (get_duel_attempt(duel, *first).solved_at.is_some() || everyone_else_gave_up)
+        .then_some(*first)
+    }
+    _ => None,
+  }
+}
+
+fn create_duel_result_embed(
+  duel: &Duel,
+  placements: &[(usize, usize)],
+  drawn: bool,
+) -> CreateEmbed {
+  let standings = placements
+    .iter()
+    .map(|(index, rank)| {
+      let player = &duel.players[*index];
+      let attempt = get_duel_attempt(duel, *index);
+      let result = match attempt.solved_at {
+        Some(solved_at) => format!("solved at {}", format_match_time(solved_at)),
+        None if duel.gave_up.contains(&player.userId) => "gave up".to_owned(),
+        None => "didn't solve".to_owned(),
+      };
+      format!("`{rank}.` <@{}> {result}", player.userId)
+    })
+    .collect::<Vec<_>>()
+    .join("\n");
+  CreateEmbed::new()
+    .title(if drawn {
+      "Duel results (draw)"
+    } else {
+      "Duel results"
+    })
+    .description(standings)
+    .colour(Colour::GOLD)
+    .timestamp(Timestamp::now())
+}
+
+// the attempt of the player on the problem of the duel, suspicious solves are reported
+async fn update_duel_attempt(ctx: &Context, duel: &mut Duel, index: usize) -> ProblemAttempt {
+  let (begin, end) = get_duel_window(duel);
+  let user = duel.players[index].clone();
+  let submission_count = 99999; // We want to get all user submissions
+  let submissions = match get_user_submission(&user.handle, submission_count).await {
+    Ok(submissions) => submissions,
+    Err(_) => return get_duel_attempt(duel, index),
+  };
+  let attempt = get_attempt(&duel.problems[0], &submissions, begin, end);
+  if attempt.solved_at.is_some() {
+    set_duel_attempt(duel, index, attempt.clone());
+    // the players who solved it before, their solve times are all we have
+    let others = (0..duel.players.len())
+      .filter(|other| *other != index)
+      .filter_map(|other| {
+        get_duel_attempt(duel, other)
+          .solved_at
+          .map(|solved_at| (duel.players[other].handle.clone(), begin + solved_at))
+      })
+      .collect::<Vec<_>>();
+    let suspicious = check_solve(&user, &duel.problems[0], &submissions, begin, end, &others);
+    report_suspicious_solves(ctx, &duel.channel_id, suspicious.into_iter().collect()).await;
+  }
+  attempt
+}
+
+/*
+  Rank the players, announce and archive the result and free everyone. After a draw the players
+  still playing aren't checked again, so none of them can win it, but a player who solved the
+  problem before keeps the win
+*/
+async fn end_duel(ctx: &Context, mut duel: Duel, drawn: bool) {
+  for index in 0..duel.players.len() {
+    if is_playing(&duel, index) && !drawn {
+      update_duel_attempt(ctx, &mut duel, index).await;
+    }
+  }
+  let placements = get_duel_placements(&duel);
+  let winner = get_duel_winner(&duel, &placements)
+    .filter(|winner| !drawn || get_duel_attempt(&duel, *winner).solved_at.is_some());
+  let message = CreateMessage::new().embed(create_duel_result_embed(&duel, &placements, drawn));
+  let _ = duel
+    .channel_id
+    .channel_id
+    .send_message(&ctx.http, message)
+    .await;
+
+  if let Some(winner) = winner {
+    let solved = get_duel_attempt(&duel, winner).solved_at.is_some();
+    let event = BotEvent::DuelWon(solved.then(|| duel.problems[0].clone()));
+    let channel_id = Some(duel.channel_id.channel_id);
+    trigger_achievements(ctx, channel_id, &duel.players[winner].userId, event).await;
+  }
+  let results = placements
+    .iter()
+    .map(|(index, rank)| {
+      let player = &duel.players[*index];
+      let attempt = get_duel_attempt(&duel, *index);
+      PlayerResult {
+        user_id: player.userId.clone(),
+        handle: player.handle.clone(),
+        rank: *rank,
+        solved: match attempt.solved_at {
+          Some(_) => vec![duel.problems[0].index.clone()],
+          None => Vec::new(),
+        },
+        wrong_attempts: attempt.wrong_attempts,
+        penalty: icpc_penalty(&attempt).unwrap_or_default(),
+      }
+    })
+    .collect::<Vec<_>>();
+  archive_match(
+    ctx,
+    MatchRecord {
+      duel_type: DuelType::DUEL,
+      channel_id: duel.channel_id.channel_id,
+      begin_time: duel.begin_time,
+      end_time: SystemTime::now(),
+      problems: duel.problems.clone(),
+      results,
+    },
+  )
+  .await;
+  let winner = winner.map(|index| duel.players[index].userId.clone());
+  finish_duel(ctx, &duel, winner).await;
+}
+
+/*
+  Every player races to solve the problem, `~match finish` records their solve time and the duel
+  goes on until every player solved it or gave up or the time is up
+*/
+pub async fn single_duel_interactor(ctx: &Context, mut duel: Duel) {
+  let msg = duel.channel_id.clone();
+  macro_rules! user_solved {
+    ($ctx: expr, $msg: expr, $user: expr, $solved_at: expr, $place: expr) => {
       let embed = CreateEmbed::new()
         .colour(Colour::BLUE)
-        .description(format!("User <@{}> wins the duel!", $user.userId))
+        .description(format!(
+          "User <@{}> solved the problem at {} after the start and takes place {}!",
+          $user.userId,
+          format_match_time($solved_at),
+          $place
+        ))
         .timestamp(Timestamp::now());
       let builder = CreateMessage::new().embed(embed);
       let _ = $msg.channel_id.send_message(&$ctx.http, builder).await;
@@ -161,10 +404,7 @@ pub async fn single_duel_interactor(ctx: &Context, duel: Duel) {
     ($ctx: expr, $msg: expr, $user: expr) => {
       let embed = CreateEmbed::new()
         .colour(Colour::RED)
-        .description(format!(
-          "User <@{}> has given up, the other user won!",
-          $user.userId
-        ))
+        .description(format!("User <@{}> has given up!", $user.userId))
         .timestamp(Timestamp::now());
       let builder = CreateMessage::new().embed(embed);
       let _ = $msg.channel_id.send_message(&$ctx.http, builder).await;
@@ -185,88 +425,64 @@ pub async fn single_duel_interactor(ctx: &Context, duel: Duel) {
     };
   }
 
-  macro_rules! no_one_wins {
-    ($ctx: expr, $msg: expr) => {
-      let embed = CreateEmbed::new()
-        .colour(Colour::BLUE)
-        .description(format!("No one wins the duel"))
-        .timestamp(Timestamp::now());
-      let builder = CreateMessage::new().embed(embed);
-      let _ = $msg.channel_id.send_message(&$ctx.http, builder).await;
-    };
-  }
-
-  let passed_time = duel.begin_time.elapsed().unwrap();
   let ctx_1 = ctx.clone();
-  let msg_1 = msg.clone();
   tokio::spawn(async move {
-    if passed_time >= DUEL_DURATION {
-      for user in duel.players.iter() {
-        if let Ok(good) = check_complete_problem(user, &duel.problems[0]).await {
-          if good.0 == false {
-            continue;
-          }
-          user_wins!(ctx_1, msg_1, user);
-          remove_duel(&ctx_1, duel.players).await;
-          return;
+    let mut drawn = false;
+    let mut votes = MatchVotes::default();
+    let mut message_collector = MessageCollector::new(&ctx_1.shard).stream();
+    // the clock only runs out while the duel isn't paused
+    while let Some(message) = next_match_message(&duel, &mut message_collector).await {
+      let user_id = message.author.id.to_string();
+      let index = match duel
+        .players
+        .iter()
+        .position(|player| player.userId == user_id)
+      {
+        Some(index) if is_playing(&duel, index) => index,
+        _ => continue,
+      };
+      if is_match_control(&message.content) {
+        let voters = (0..duel.players.len())
+          .filter(|index| is_playing(&duel, *index))
+          .map(|index| duel.players[index].userId.clone())
+          .collect::<Vec<_>>();
+        if handle_match_control(&ctx_1, &mut duel, &mut votes, &message, &voters).await {
+          drawn = true;
+          break;
         }
+        continue;
       }
-      no_one_wins!(ctx_1, msg_1);
-      remove_duel(&ctx_1, duel.players).await;
-      return;
-    }
-
-    let mut message_collector = MessageCollector::new(&ctx_1.shard)
-      .timeout(DUEL_DURATION - passed_time)
-      .stream();
-
-    loop {
-      if let Some(message) = message_collector.next().await {
-        if message.content != format!("~match giveup")
-          && message.content != format!("~match finish")
-        {
-          continue;
-        }
-        let user_wrap = find_user_in_data(&ctx_1, &message.author.id.to_string()).await;
-
-        if let Err(why) = user_wrap {
-          error_response!(ctx_1, msg_1, why);
-          continue;
-        }
-        let user = user_wrap.unwrap();
-        let have_user = |user: &User| {
-          for player in duel.players.iter() {
-            if player.userId == user.userId {
-              return true;
-            }
-          }
-          false
-        };
-        if have_user(&user) && message.content == format!("~match finish") {
-          let is_complete = check_complete_problem(&user, &duel.problems[0]).await;
-          if let Ok(good) = is_complete {
-            if good.0 == true {
-              user_wins!(ctx_1, msg_1, user);
-              remove_duel(&ctx_1, duel.players).await;
-              return;
-            }
-          } else {
-            user_no_complete!(ctx_1, msg_1, user);
+      if message.content != "~match giveup" && message.content != "~match finish" {
+        continue;
+      }
+      let user = duel.players[index].clone();
+      if message.content == "~match finish" {
+        let attempt = update_duel_attempt(&ctx_1, &mut duel, index).await;
+        let solved_at = match attempt.solved_at {
+          Some(solved_at) => solved_at,
+          None => {
+            user_no_complete!(ctx_1, msg, user);
             continue;
           }
-        }
-        if have_user(&user) && message.content == format!("~match giveup") {
-          user_giveup!(ctx_1, msg_1, user);
-          remove_duel(&ctx_1, duel.players).await;
-          return;
-        }
+        };
+        let place = get_duel_placements(&duel)
+          .into_iter()
+          .find(|(player, _)| *player == index)
+          .map_or(1, |(_, rank)| rank);
+        user_solved!(ctx_1, msg, user, solved_at, place);
       } else {
+        duel.gave_up.push(user_id);
+        user_giveup!(ctx_1, msg, user);
+      }
+      save_duel(&ctx_1, &duel).await;
+      let playing = (0..duel.players.len())
+        .filter(|index| is_playing(&duel, *index))
+        .count();
+      if playing == 0 {
         break;
       }
     }
-
-    no_one_wins!(ctx_1, msg_1);
-    remove_duel(&ctx_1, duel.players).await;
+    end_duel(&ctx_1, duel, drawn).await;
   });
 }
 
@@ -339,6 +555,36 @@ pub async fn handle_args(
   Ok((opponents, opt))
 }
 
+// split a `pool=...` argument from the other arguments of a command
+pub fn take_pool_arg(args: &Args) -> Result<(Args, Option<ProblemPool>), String> {
+  let mut pool = None;
+  let mut rest = Vec::new();
+  for arg in args.raw() {
+    match arg.strip_prefix("pool=") {
+      Some(spec) => pool = Some(ProblemPool::parse(spec)?),
+      None => rest.push(arg),
+    }
+  }
+  Ok((Args::new(&rest.join(" "), &[Delimiter::Single(' ')]), pool))
+}
+
+// split a `fair` argument from the other arguments, it asks for a problem nobody has seen
+pub fn take_fair_arg(args: &Args) -> (Args, ProblemFilter) {
+  let mut filter = ProblemFilter::default();
+  let mut rest = Vec::new();
+  for arg in args.raw() {
+    if arg == "fair" {
+      filter = ProblemFilter::fair();
+    } else {
+      rest.push(arg);
+    }
+  }
+  (
+    Args::new(&rest.join(" "), &[Delimiter::Single(' ')]),
+    filter,
+  )
+}
+
 pub async fn collect_messages(
   ctx: &Context,
   msg: &Message,
@@ -453,6 +699,15 @@ pub async fn duel(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
     msg.channel_id.send_message(&ctx.http, message).await?;
     return Ok(());
   }
+  let (args, requested_pool) = match take_pool_arg(&args) {
+    Ok(parsed) => parsed,
+    Err(why) => {
+      error_response!(ctx, msg, why);
+      return Ok(());
+    }
+  };
+  let pool = get_problem_pool(ctx, msg.guild_id, requested_pool).await;
+  let (args, filter) = take_fair_arg(&args);
   let args_result = handle_args(
     &ctx,
     &msg,
@@ -497,28 +752,11 @@ pub async fn duel(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
 
     let parsed_rating: u32 = match rating {
       Some(parsed) => parsed,
-      None => {
-        let mut sum = 0;
-        let mut count = 0;
-        for user in users_in_duel.iter() {
-          let user_rating = get_user_rating(&user.handle).await;
-          if let Err(_) = user_rating {
-            continue;
-          }
-          sum += user_rating.unwrap();
-          count += 1;
-        }
-
-        if sum == 0 {
-          sum = DEFAULT_RATING as u32;
-          count = 1;
-        }
-        ((sum) / count as u32) / 100 * 100
-      }
+      None => get_average_rating(&users_in_duel).await,
     };
     msg.channel_id.say(&ctx.http, "Duel accepted").await?;
 
-    handle_duel(&ctx, &msg, users_in_duel, parsed_rating).await;
+    handle_duel(ctx, msg, users_in_duel, parsed_rating, &pool, &filter).await;
   } else {
     error_response!(
       ctx,
@@ -19,7 +19,11 @@ use crate::commands::handle::*;
 use crate::commands::lockout::*;
 use crate::commands::rating::*;
 
+use crate::core::achievement::*;
 use crate::core::data::{self, User, *};
+use crate::core::estimator::estimate_difficulties;
+use crate::core::pool::ProblemPool;
+use crate::core::scoring::get_first_accepted;
 use crate::utils::message_creator::*;
 
 use crate::error_response;
@@ -33,6 +37,63 @@ pub const MAX_RATING: u32 = 3500;
 pub const MIN_RATING: u32 = 800;
 pub const MAX_ICPC_PROBLEM_REQUEST: u8 = 13;
 pub const ICPC_YEAR_FILTER: Option<u32> = Some(2018);
+const PROBLEMSET_CACHE_DURATION: Duration = Duration::from_secs(60 * 10);
+
+// problemset.problems is a big response, keep the last one around for a while
+static PROBLEMSET_CACHE: RwLock<Option<(SystemTime, Vec<Problem>)>> = RwLock::const_new(None);
+
+// which problems the user already submitted to are still allowed
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum AttemptFilter {
+  // only problems with an `OK` verdict are removed
+  #[default]
+  ExcludeSolved,
+  // any problem with a submission is removed
+  ExcludeAttempted,
+  // only problems that were attempted but never solved are kept
+  OnlyAttempted,
+}
+
+// tag filters for `~giveme`, tags are resolved against the problemset before filtering
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ProblemFilter {
+  pub include_tags: Vec<String>,
+  pub exclude_tags: Vec<String>,
+  pub attempts: AttemptFilter,
+  // problems of contests the user took part in (not as practice) are removed
+  pub exclude_participated: bool,
+}
+
+impl ProblemFilter {
+  // nobody in the match attempted the problem or took part in its contest
+  pub fn fair() -> ProblemFilter {
+    ProblemFilter {
+      attempts: AttemptFilter::ExcludeAttempted,
+      exclude_participated: true,
+      ..Default::default()
+    }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.include_tags.is_empty()
+      && self.exclude_tags.is_empty()
+      && self.attempts == AttemptFilter::ExcludeSolved
+      && !self.exclude_participated
+  }
+
+  // replace every tag with the problemset tag it completes to
+  pub fn resolve_tags(&mut self, problems: &[Problem]) -> Result<(), String> {
+    let tags = collect_tags(problems);
+    for tag in self
+      .include_tags
+      .iter_mut()
+      .chain(self.exclude_tags.iter_mut())
+    {
+      *tag = resolve_tag(tag, &tags)?;
+    }
+    Ok(())
+  }
+}
 
 #[allow(non_snake_case)]
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -84,7 +145,7 @@ fn weight(x: usize, n: usize, alpha: f64) -> f64 {
 async fn show_help() -> CreateMessage {
   let embed = CreateEmbed::new()
     .title(format!("Usage of `giveme`"))
-    .description(format!("`~giveme practice [rating / ranting_range]`\n`~giveme challenge [delta / delta_range]`\n`~giveme help`\n`~giveme icpc [number of problem]` (this will recommend icpc problems using normal distribution probability)"))
+    .description(format!("`~giveme practice [rating / ranting_range] [+tag / -tag ...] [fresh / revenge]`\n`~giveme challenge [delta / delta_range] [+tag / -tag ...] [fresh / revenge]`\n(`+dp` only keeps problems with the tag, `-interactive` removes them, write spaces as `_` like `+binary_search`, tags are completed from their prefix)\n(`fresh` skips every problem you have submitted to, `revenge` only gives problems you have attempted but not solved yet)\n(`pool=div2,div3,edu,ids:1500-1900,year:2020` picks problems from other contests than the server pool)\n`~giveme help`\n`~giveme icpc [number of problem]` (this will recommend icpc problems using normal distribution probability)"))
     .color(Colour::DARK_GREEN);
   let builder = CreateMessage::new().embed(embed);
   builder
@@ -113,9 +174,25 @@ pub async fn find_user_in_data(ctx: &Context, user_id: &String) -> Result<User,
 }
 
 pub async fn get_contest_standing(contest_id: u32) -> Result<ContestResults, String> {
-  let client = Client::new();
   let max_count = 100000;
   let url = format!("https://codeforces.com/api/contest.standings?contestId={contest_id}&from=1&count={max_count}&showUnofficial=false");
+  fetch_contest_standing(url).await
+}
+
+// the rows of `handles` only, unofficial participants included
+pub async fn get_contest_standing_of_handles(
+  contest_id: u32,
+  handles: &[String],
+) -> Result<ContestResults, String> {
+  let url = format!(
+    "https://codeforces.com/api/contest.standings?contestId={contest_id}&handles={handles}&showUnofficial=true",
+    handles = handles.join(";")
+  );
+  fetch_contest_standing(url).await
+}
+
+async fn fetch_contest_standing(url: String) -> Result<ContestResults, String> {
+  let client = Client::new();
   let http_result = client.get(url).send().await;
   match http_result {
     Ok(res) => match handle_api_response::<APIContestStandingResponse>(res).await {
@@ -133,16 +210,28 @@ pub async fn get_contest_standing(contest_id: u32) -> Result<ContestResults, Str
 }
 
 pub async fn get_problemset() -> Result<Vec<Problem>, String> {
+  if let Some((fetched_at, problems)) = &*PROBLEMSET_CACHE.read().await {
+    if fetched_at.elapsed().unwrap_or(PROBLEMSET_CACHE_DURATION) < PROBLEMSET_CACHE_DURATION {
+      return Ok(problems.clone());
+    }
+  }
   let client = Client::new();
   let url = format!("https://codeforces.com/api/problemset.problems");
   let http_result = client.get(url).send().await;
   match http_result {
     Ok(res) => match handle_api_problemset_response(res).await {
       Ok(json_object) => {
-        let problems = json_object.result.problems;
+        let mut problems = json_object.result.problems;
         if problems.len() == 0 {
           return Err(format!("No problems to suggest!"));
         }
+        let contests = get_contests(false).await.unwrap_or_default();
+        estimate_difficulties(
+          &mut problems,
+          &json_object.result.problemStatistics,
+          &contests,
+        );
+        *PROBLEMSET_CACHE.write().await = Some((SystemTime::now(), problems.clone()));
         return Ok(problems);
       }
       Err(why) => {
@@ -162,37 +251,176 @@ async fn handle_uncomplete_challenge(user: &User) -> Result<(), String> {
   return Err(format!("You still have an active challenge!"));
 }
 
+// every distinct tag of the problemset in sorted order
+pub fn collect_tags(problems: &[Problem]) -> Vec<String> {
+  let mut tags = problems
+    .iter()
+    .flat_map(|problem| problem.tags.iter().cloned())
+    .collect::<Vec<_>>();
+  tags.sort();
+  tags.dedup();
+  tags
+}
+
+/*
+  Complete a tag typed by the user (`binary_search`, `graph`, `constr`...) to a tag of the
+  problemset. An exact match wins, otherwise the prefix has to be unambiguous
+*/
+pub fn resolve_tag(input: &str, tags: &[String]) -> Result<String, String> {
+  let wanted = input.trim().replace('_', " ").to_lowercase();
+  if let Some(tag) = tags.iter().find(|tag| **tag == wanted) {
+    return Ok(tag.clone());
+  }
+  let mut candidates = tags
+    .iter()
+    .filter(|tag| tag.starts_with(&wanted))
+    .collect::<Vec<_>>();
+  if candidates.is_empty() {
+    candidates = tags.iter().filter(|tag| tag.contains(&wanted)).collect();
+  }
+  if candidates.len() == 1 {
+    return Ok(candidates[0].clone());
+  }
+  if candidates.is_empty() {
+    return Err(format!(
+      "Unknown tag `{input}`, available tags are: {}",
+      tags
+        .iter()
+        .map(|tag| format!("`{}`", tag.replace(' ', "_")))
+        .collect::<Vec<_>>()
+        .join(", ")
+    ));
+  }
+  Err(format!(
+    "Tag `{input}` is ambiguous, did you mean: {}",
+    candidates
+      .iter()
+      .map(|tag| format!("`{}`", tag.replace(' ', "_")))
+      .collect::<Vec<_>>()
+      .join(", ")
+  ))
+}
+
+// keep the problems matching `keep` and remember how many candidates the step removed
+fn retain_counted(
+  problems: &mut Vec<Problem>,
+  removed: &mut Vec<(String, usize)>,
+  step: String,
+  keep: impl Fn(&Problem) -> bool,
+) {
+  let before = problems.len();
+  problems.retain(|problem| keep(problem));
+  removed.push((step, before - problems.len()));
+}
+
+// the contests the user submitted to as a contestant, virtually or out of competition
+pub fn get_participated_contests(user_submission: &[Submission]) -> Vec<u32> {
+  let mut contests = user_submission
+    .iter()
+    .filter(|submission| submission.author.participantType != "PRACTICE")
+    .filter_map(|submission| submission.contestId)
+    .collect::<Vec<_>>();
+  contests.sort_unstable();
+  contests.dedup();
+  contests
+}
+
 // The same as get_problems but you give the problem_set to filter
 pub async fn get_problems_with_given_problemset(
   mut rating_range: u32,
   mut problems: Vec<Problem>,
   user_submission: Vec<Submission>,
+  filter: &ProblemFilter,
 ) -> Result<Vec<Problem>, String> {
-  rating_range = ((rating_range + 100 - 1) / 100) * 100;
-  problems = problems
-    .into_iter()
-    .filter(|problem| {
-      if let Some(rating) = problem.rating {
-        return rating == rating_range as i32;
-      } else {
-        return false;
-      }
+  rating_range = rating_range.div_ceil(100) * 100;
+  let total = problems.len();
+  let mut removed: Vec<(String, usize)> = Vec::new();
+  retain_counted(
+    &mut problems,
+    &mut removed,
+    format!("rating {rating_range}"),
+    |problem| problem.difficulty() == Some(rating_range as i32),
+  );
+
+  for tag in filter.include_tags.iter() {
+    retain_counted(&mut problems, &mut removed, format!("+{tag}"), |problem| {
+      problem.tags.contains(tag)
+    });
+  }
+  for tag in filter.exclude_tags.iter() {
+    retain_counted(&mut problems, &mut removed, format!("-{tag}"), |problem| {
+      !problem.tags.contains(tag)
+    });
+  }
+
+  let solved = |problem: &Problem| {
+    user_submission.iter().any(|submission| {
+      submission.problem == *problem && submission.verdict.as_deref() == Some("OK")
     })
-    .collect::<Vec<_>>();
-
-  problems = problems
-    .into_iter()
-    .filter(|problem| {
-      !user_submission.iter().any(|submission| {
-        submission.problem == *problem
-          && submission.verdict != None
-          && submission.verdict.clone().unwrap() == "OK"
-      })
-    })
-    .collect::<Vec<_>>();
+  };
+  let attempted = |problem: &Problem| {
+    user_submission
+      .iter()
+      .any(|submission| submission.problem == *problem)
+  };
+  match filter.attempts {
+    AttemptFilter::ExcludeSolved => {
+      retain_counted(
+        &mut problems,
+        &mut removed,
+        "already solved".to_owned(),
+        |problem| !solved(problem),
+      );
+    }
+    AttemptFilter::ExcludeAttempted => {
+      retain_counted(
+        &mut problems,
+        &mut removed,
+        "fresh (already attempted)".to_owned(),
+        |problem| !attempted(problem),
+      );
+    }
+    AttemptFilter::OnlyAttempted => {
+      retain_counted(
+        &mut problems,
+        &mut removed,
+        "already solved".to_owned(),
+        |problem| !solved(problem),
+      );
+      retain_counted(
+        &mut problems,
+        &mut removed,
+        "revenge (never attempted)".to_owned(),
+        attempted,
+      );
+    }
+  }
+  if filter.exclude_participated {
+    let participated = get_participated_contests(&user_submission);
+    retain_counted(
+      &mut problems,
+      &mut removed,
+      "fair (contest taken part in)".to_owned(),
+      |problem| {
+        problem
+          .contestId
+          .is_none_or(|contest_id| !participated.contains(&contest_id))
+      },
+    );
+  }
 
-  if problems.len() == 0 {
-    return Err(format!("We can't provide a suitable problem for you"));
+  if problems.is_empty() {
+    if filter.is_empty() {
+      return Err(format!("We can't provide a suitable problem for you"));
+    }
+    let breakdown = removed
+      .iter()
+      .map(|(step, count)| format!("`{step}` removed {count} problem(s)"))
+      .collect::<Vec<_>>()
+      .join("\n");
+    return Err(format!(
+      "We can't provide a suitable problem for you, out of {total} candidates:\n{breakdown}"
+    ));
   }
 
   problems.sort_by(|a, b| {
@@ -212,19 +440,18 @@ pub async fn get_problems_with_given_problemset(
  */
 pub async fn get_icpc_problems(
   mut problem_count: u32,
-  handle: &String,
+  handles: &[String],
 ) -> Result<Vec<Problem>, String> {
   let contests_wrap = get_contests(true).await;
   if let Err(why) = contests_wrap {
     return Err(why);
   }
   let max_submission = 999999;
-  let submissions_wrap = get_user_submission(&handle, max_submission).await;
-  if let Err(why) = submissions_wrap {
-    return Err(why);
+  let mut submissions = Vec::<Submission>::new();
+  for handle in handles.iter() {
+    submissions.extend(get_user_submission(handle, max_submission).await?);
   }
 
-  let submissions = submissions_wrap.unwrap();
   let contests = contests_wrap.unwrap();
 
   let mut problems = Vec::<Problem>::new();
@@ -262,9 +489,10 @@ pub async fn get_icpc_problems(
     let distribution = WeightedIndex::new(&weights).unwrap();
     let mut rng = thread_rng();
     let picked_problem = contest_problems[indices[distribution.sample(&mut rng)]].clone();
-    if submissions
-      .iter()
-      .any(|sub| sub.problem == picked_problem && sub.verdict == Some(format!("OK")))
+    if problems.contains(&picked_problem)
+      || submissions
+        .iter()
+        .any(|sub| sub.problem == picked_problem && sub.verdict.as_deref() == Some("OK"))
     {
       continue;
     }
@@ -280,17 +508,19 @@ pub async fn get_icpc_problems(
 
 // return a vector of unsolved problems for some user within the `rating_range`
 // (first half of the current `recommend problem`) in sorted order
-pub async fn get_problems(user: &String, rating_range: u32) -> Result<Vec<Problem>, String> {
-  let problems_wrap = get_problemset().await;
+pub async fn get_problems(
+  user: &String,
+  rating_range: u32,
+  filter: &ProblemFilter,
+  pool: &ProblemPool,
+) -> Result<Vec<Problem>, String> {
+  let mut filter = filter.clone();
+  filter.resolve_tags(&get_problemset().await?)?;
+  let problems_wrap = get_pool_problemset(pool).await;
   if let Err(why) = problems_wrap {
     return Err(why);
   }
-  let contests_wrap = get_contests(false).await;
-  if let Err(why) = contests_wrap {
-    return Err(why);
-  }
-  let mut problems = problems_wrap.unwrap();
-  problems = filter_problemset(problems, contests_wrap.unwrap());
+  let problems = problems_wrap.unwrap();
 
   let submission_count = 99999; // We want to get all user submissions
   let user_submission_wrap = get_user_submission(&user, submission_count).await;
@@ -299,7 +529,7 @@ pub async fn get_problems(user: &String, rating_range: u32) -> Result<Vec<Proble
   }
 
   let user_submission = user_submission_wrap.unwrap();
-  get_problems_with_given_problemset(rating_range, problems.clone(), user_submission).await
+  get_problems_with_given_problemset(rating_range, problems.clone(), user_submission, &filter).await
 }
 
 // Vec<Problem> needs to be sorted
@@ -365,8 +595,13 @@ pub fn get_icpc_contest_with_weights(mut contests: Vec<Contest>) -> Contest {
   contests[distribution.sample(&mut rng)].clone()
 }
 
-async fn recommend_problem(user: &String, rating_range: u32) -> Result<Problem, String> {
-  match get_problems(&user, rating_range).await {
+async fn recommend_problem(
+  user: &String,
+  rating_range: u32,
+  filter: &ProblemFilter,
+  pool: &ProblemPool,
+) -> Result<Problem, String> {
+  match get_problems(user, rating_range, filter, pool).await {
     Ok(problems) => {
       return Ok(get_problem_with_weights(problems));
     }
@@ -455,7 +690,8 @@ async fn giveme(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
       .await
       .unwrap();
     let start_time = SystemTime::now();
-    let problems_wrap = get_icpc_problems(problem_count.unwrap(), &user.handle).await;
+    let problems_wrap =
+      get_icpc_problems(problem_count.unwrap(), std::slice::from_ref(&user.handle)).await;
     if let Err(_) = problems_wrap {
       let _ = edit_to_failed_status(ctx, message).await;
       return Ok(());
@@ -507,6 +743,35 @@ async fn giveme(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
     return Ok(());
   }
 
+  let mut filter = ProblemFilter::default();
+  let mut requested_pool: Option<ProblemPool> = None;
+  while let Ok(arg) = args.single::<String>() {
+    if let Some(spec) = arg.strip_prefix("pool=") {
+      match ProblemPool::parse(spec) {
+        Ok(pool) => requested_pool = Some(pool),
+        Err(why) => {
+          error_response!(ctx, msg, why);
+          return Ok(());
+        }
+      }
+    } else if let Some(tag) = arg.strip_prefix('+').filter(|tag| !tag.is_empty()) {
+      filter.include_tags.push(tag.to_owned());
+    } else if let Some(tag) = arg.strip_prefix('-').filter(|tag| !tag.is_empty()) {
+      filter.exclude_tags.push(tag.to_owned());
+    } else if arg == "fresh" {
+      filter.attempts = AttemptFilter::ExcludeAttempted;
+    } else if arg == "revenge" {
+      filter.attempts = AttemptFilter::OnlyAttempted;
+    } else {
+      error_response!(
+        ctx,
+        msg,
+        format!("Unknown argument `{arg}`, use `+tag` to include or `-tag` to exclude a tag, `fresh` to skip attempted problems, `revenge` to only get them or `pool=...` to pick the contests")
+      );
+      return Ok(());
+    }
+  }
+
   if give_type == "challenge" || give_type == "c" {
     if let Err(why) = handle_uncomplete_challenge(&user).await {
       error_response!(ctx, msg, why);
@@ -545,7 +810,8 @@ async fn giveme(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
 
   rating = Some(cmp::min(cmp::max(rating.unwrap(), MIN_RATING), MAX_RATING));
 
-  match recommend_problem(&user.handle, rating.unwrap()).await {
+  let pool = get_problem_pool(ctx, msg.guild_id, requested_pool).await;
+  match recommend_problem(&user.handle, rating.unwrap(), &filter, &pool).await {
     Ok(problem) => {
       let message = create_problem_message(
         &problem,
@@ -639,38 +905,31 @@ pub fn convert_to_hms(elapsed_time: &Duration) -> (u64, u64, u64) {
   )
 }
 
+/*
+  Only submissions made in [begin, end) count, so a problem solved before a match started doesn't
+  win it. Returns the rating of the problem and the time of the first accepted submission
+*/
 pub async fn check_complete_problem_with_given_submission(
   problem: &Problem,
   submissions: Vec<Submission>,
+  begin: u64,
+  end: u64,
 ) -> Result<(bool, i32, u64), String> {
-  let mut status = false;
-  let mut problem_rating: Option<i32> = None;
-  let mut creation_time: Option<u64> = None;
-  submissions.iter().for_each(|submission| {
-    if let Some(verdict) = submission.verdict.clone() {
-      if submission.problem == *problem && verdict == format!("OK") {
-        problem_rating = problem.rating;
-        if creation_time == None {
-          creation_time = Some(submission.creationTimeSeconds);
-        } else {
-          creation_time = Some(cmp::min(
-            creation_time.unwrap(),
-            submission.creationTimeSeconds,
-          ));
-        }
-        status = true;
-      }
-    }
-  });
-  if status == false {
-    return Err(format!("The problem hasn't been completed"));
+  match get_first_accepted(problem, &submissions, begin, end) {
+    Some(creation_time) => Ok((
+      true,
+      problem.difficulty().unwrap_or(MIN_RATING as i32),
+      creation_time,
+    )),
+    None => Err("The problem hasn't been completed".to_owned()),
   }
-  Ok((status, problem_rating.unwrap(), creation_time.unwrap()))
 }
 
 pub async fn check_complete_problem(
   user: &User,
   problem: &Problem,
+  begin: u64,
+  end: u64,
 ) -> Result<(bool, i32, u64), String> {
   let submission_count = 99999; // We want to get all user submissions
   let user_submission_wrap = get_user_submission(&user.handle, submission_count).await;
@@ -680,7 +939,7 @@ pub async fn check_complete_problem(
   }
   let submissions = user_submission_wrap.unwrap();
 
-  check_complete_problem_with_given_submission(problem, submissions.clone()).await
+  check_complete_problem_with_given_submission(problem, submissions.clone(), begin, end).await
 }
 
 #[command]
@@ -698,7 +957,7 @@ pub async fn gotit(ctx: &Context, msg: &Message) -> CommandResult {
     return Ok(());
   }
   let problem = user.clone().active_challenge.unwrap();
-  let status = check_complete_problem(&user, &problem).await;
+  let status = check_complete_problem(&user, &problem, 0, u64::MAX).await;
   if let Err(why) = status {
     error_response!(ctx, msg, why);
     return Ok(());
@@ -724,6 +983,8 @@ pub async fn gotit(ctx: &Context, msg: &Message) -> CommandResult {
       .embed(embed);
     msg.channel_id.send_message(&ctx.http, builder).await?;
     remove_problem_from_user(ctx, &user_id).await?;
+    let event = BotEvent::ChallengeCompleted(problem);
+    trigger_achievements(ctx, Some(msg.channel_id), &user_id, event).await;
   }
   Ok(())
 }
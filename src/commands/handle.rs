@@ -23,7 +23,7 @@ use crate::{add_user_to_data, get_problemset, UserData};
 const WAIT_TIME: u64 = 60;
 
 #[allow(non_snake_case)]
-#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct Problem {
   pub contestId: Option<u32>,
   pub problemsetName: Option<String>,
@@ -33,46 +33,73 @@ pub struct Problem {
   pub points: Option<f32>,
   pub rating: Option<i32>,
   pub tags: Vec<String>,
+  // filled from the solve count when codeforces hasn't rated the problem
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub estimated_rating: Option<i32>,
+}
+
+impl Problem {
+  // the codeforces rating, or the estimated one for unrated problems
+  pub fn difficulty(&self) -> Option<i32> {
+    self.rating.or(self.estimated_rating)
+  }
+
+  pub fn difficulty_string(&self) -> Option<String> {
+    match (self.rating, self.estimated_rating) {
+      (Some(rating), _) => Some(rating.to_string()),
+      (None, Some(estimated)) => Some(format!("~{estimated} (estimated)")),
+      (None, None) => None,
+    }
+  }
+}
+
+// a problem is identified by where it was published, ratings and tags of a problem change
+impl PartialEq for Problem {
+  fn eq(&self, other: &Self) -> bool {
+    self.contestId == other.contestId
+      && self.problemsetName == other.problemsetName
+      && self.index == other.index
+  }
 }
 
 #[allow(non_snake_case)]
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ProblemStatistic {
-  contestId: Option<u32>,
-  index: String,
+  pub contestId: Option<u32>,
+  pub index: String,
   pub solvedCount: i32,
 }
 
 #[allow(non_snake_case)]
 #[derive(Serialize, Deserialize, Debug, Clone)]
-struct Member {
-  handle: String,
-  name: Option<String>,
+pub struct Member {
+  pub handle: String,
+  pub name: Option<String>,
 }
 
 #[allow(non_snake_case)]
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Author {
-  contestId: Option<u32>,
-  members: Vec<Member>,
-  participantType: String,
-  teamId: Option<i32>,
-  teamName: Option<String>,
-  ghost: bool,
-  room: Option<i32>,
-  startTimeSeconds: Option<u64>,
+  pub contestId: Option<u32>,
+  pub members: Vec<Member>,
+  pub participantType: String,
+  pub teamId: Option<i32>,
+  pub teamName: Option<String>,
+  pub ghost: bool,
+  pub room: Option<i32>,
+  pub startTimeSeconds: Option<u64>,
 }
 
 #[allow(non_snake_case)]
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Submission {
-  id: u64,
+  pub id: u64,
   pub contestId: Option<u32>,
   pub creationTimeSeconds: u64,
   relativeTimeSeconds: u64,
   pub problem: Problem,
-  author: Author,
-  programmingLanguage: String,
+  pub author: Author,
+  pub programmingLanguage: String,
   pub verdict: Option<String>,
   testset: String,
   passedTestCount: u32,
@@ -6,7 +6,7 @@ use std::time::SystemTime;
 use serenity::builder::{CreateEmbed, CreateMessage};
 use serenity::collector::MessageCollector;
 use serenity::framework::standard::macros::command;
-use serenity::framework::standard::{Args, CommandResult};
+use serenity::framework::standard::{Args, CommandResult, Delimiter};
 use serenity::futures::StreamExt;
 use serenity::model::prelude::*;
 use serenity::prelude::*;
@@ -17,11 +17,18 @@ use tracing::info;
 use crate::commands::duel::*;
 use crate::commands::giveme::*;
 use crate::commands::handle::*;
+use crate::commands::match_control::*;
+use crate::commands::veto::*;
 
+use crate::core::achievement::*;
+use crate::core::anticheat::*;
 use crate::core::data::User;
 use crate::core::data::*;
+use crate::core::pool::ProblemPool;
+use crate::core::scoring::*;
 
 use crate::utils::message_creator::*;
+use crate::utils::time::*;
 
 const WAIT_DURATION: Duration = Duration::from_millis(1000 * 30);
 const DEFAULT_PROBLEM_COUNT: i32 = 5;
@@ -32,9 +39,14 @@ async fn show_help() -> CreateMessage {
   let embed = CreateEmbed::new()
     .title(format!("Usage of `lockoout`"))
     .description(format!(
-      "`~lockout <@user1> <@user2> ... <@usern> (type `1` or leave empty)`\n
+      "`~lockout <@user1> <@user2> ... <@usern> (type `1` or leave empty) [pool=div2,edu,...] [fair]` (`fair` skips problems anyone attempted and contests anyone took part in)\n
+      `~lockout <@user1> ... scoring=icpc` (everyone can solve every problem, ranked by solved count then penalty)\n
+      `~lockout <@user1> ... scoring=cf [penalty=50]` (the points of a problem decay over time and every rejected submission before the claim costs the penalty)\n
+      `~lockout <@teammate> ... vs <@user1> <@user2> ... [vs ...]` (team lockout, a problem goes to the team of whoever solves it first)\n
       `~match update (get match's current status)`\n
-      `~match giveup (give up like a loser)`"
+      `~match giveup (give up like a loser)`\n
+      `~match draw` / `~match extend <minutes>` (ends the match as a draw or adds time once every player asked for it)\n
+      `~match pause` / `~match resume` (freeze the clock, e.g. while Codeforces is down)"
     ))
     .color(Colour::DARK_GREEN);
   let builder = CreateMessage::new().embed(embed);
@@ -68,38 +80,52 @@ fn create_ratings_array(
   score_array
 }
 
-// filter problem from problemset to make sure they only belong to codeforces round
-pub fn filter_problemset(problem_set: Vec<Problem>, mut contests: Vec<Contest>) -> Vec<Problem> {
-  let match_str = "Codeforces Round";
+// filter problem from problemset to make sure they only belong to contests of the pool
+pub fn filter_problemset(
+  problem_set: Vec<Problem>,
+  mut contests: Vec<Contest>,
+  pool: &ProblemPool,
+) -> Vec<Problem> {
   contests = contests
     .into_iter()
-    .filter(|contest| contest.name.contains(match_str))
+    .filter(|contest| pool.contains(contest))
     .collect::<Vec<_>>();
   problem_set
     .into_iter()
     .filter(|problem| {
       contests
         .iter()
-        .any(|contest| contest.id == problem.contestId.unwrap())
+        .any(|contest| Some(contest.id) == problem.contestId)
     })
     .collect::<Vec<_>>()
 }
 
+// the problemset restricted to the contests of `pool`
+pub async fn get_pool_problemset(pool: &ProblemPool) -> Result<Vec<Problem>, String> {
+  let problem_set = get_problemset().await?;
+  let contests = get_contests(false).await?;
+  let problems = filter_problemset(problem_set, contests, pool);
+  if problems.is_empty() {
+    return Err(format!(
+      "There are no problems in the pool `{}`",
+      pool.describe()
+    ));
+  }
+  Ok(problems)
+}
+
 async fn provide_problems_with_ratings(
   users: &Vec<User>,
   ratings_array: &Vec<u32>,
+  pool: &ProblemPool,
+  filter: &ProblemFilter,
 ) -> Option<(Vec<Problem>, Vec<u32>)> {
-  let problems_wrap = get_problemset().await;
+  let problems_wrap = get_pool_problemset(pool).await;
   if let Err(_) = problems_wrap {
     return None;
   }
-  let contests_wrap = get_contests(false).await;
-  if let Err(_) = contests_wrap {
-    return None;
-  }
 
-  let mut problem_set = problems_wrap.unwrap();
-  problem_set = filter_problemset(problem_set, contests_wrap.unwrap());
+  let problem_set = problems_wrap.unwrap();
   let number_of_problems = ratings_array.len();
   let mut problems: Vec<Problem> = Vec::new();
   let user_submissionns = get_all_user_submissions(users).await;
@@ -109,7 +135,7 @@ async fn provide_problems_with_ratings(
   let current_time = SystemTime::now();
   for (i, rating) in ratings_array.iter().enumerate() {
     if let Some(problem) =
-      get_problem_for_users(&users, *rating, &problem_set, &user_submissionns).await
+      get_problem_for_users(users, *rating, &problem_set, &user_submissionns, filter).await
     {
       problems.push(problem);
     } else {
@@ -126,10 +152,15 @@ async fn handle_lockout(
   ctx: &Context,
   msg: &Message,
   users: Vec<User>,
+  teams: Option<Vec<usize>>,
+  scoring: Option<ScoringStyle>,
+  wrong_penalty: Option<u32>,
   number_of_problems: u32,
   lockout_duration: Duration,
   lockout_rating: u32,
   lockout_increment: u32,
+  pool: &ProblemPool,
+  filter: &ProblemFilter,
 ) {
   let builder = create_await_message();
   let message = msg
@@ -140,7 +171,7 @@ async fn handle_lockout(
 
   let ratings_array = create_ratings_array(number_of_problems, lockout_rating, lockout_increment);
 
-  let parsed = provide_problems_with_ratings(&users, &ratings_array).await;
+  let parsed = provide_problems_with_ratings(&users, &ratings_array, pool, filter).await;
   if parsed == None {
     let _ = edit_to_failed_status(&ctx, message).await;
     return;
@@ -148,12 +179,38 @@ async fn handle_lockout(
   let (problems, problems_point) = parsed.unwrap();
 
   let (_, minutes, hours) = convert_to_hms(&lockout_duration);
+  // only decaying points have a penalty
+  let wrong_penalty = match scoring {
+    Some(ScoringStyle::Codeforces) => Some(wrong_penalty.unwrap_or(CF_WRONG_PENALTY as u32)),
+    _ => None,
+  };
 
-  let _ = msg.channel_id.say(&ctx.http, format!("Compete for {hours} hour(s) and {minutes} minute(s)\nType `~match update` to update the status of the lockout!\n")).await;
-
-  create_lockout(ctx, msg, users, &problems, lockout_duration, problems_point).await;
+  // the players are busy during the veto, the clock starts once it is over
+  let mut lockout_match = create_lockout(
+    ctx,
+    msg,
+    users,
+    &problems,
+    lockout_duration,
+    problems_point,
+    teams,
+    scoring,
+    wrong_penalty,
+  )
+  .await;
+  veto_problems(
+    ctx,
+    msg,
+    &lockout_match.players,
+    &mut lockout_match.problems,
+    pool,
+    filter,
+  )
+  .await;
+  lockout_match.begin_time = SystemTime::now();
+  save_duel(ctx, &lockout_match).await;
 
-  let lockout_match = get_duels(&ctx).await.unwrap().last().unwrap().clone();
+  let _ = msg.channel_id.say(&ctx.http, format!("Compete for {hours} hour(s) and {minutes} minute(s)\nType `~match update` to update the status of the lockout!\n")).await;
   let _ = edit_to_lockout_status(&ctx, &lockout_match, message, true).await;
   single_lockout_interactor(&ctx, lockout_match).await;
 }
@@ -172,16 +229,60 @@ pub fn get_leaderboard_indices(lockout: &Duel) -> Vec<usize> {
   indices
 }
 
+// the points of every team, which are the points its players claimed
+pub fn get_team_scores(lockout: &Duel) -> Option<Vec<u32>> {
+  let teams = lockout.teams.as_ref()?;
+  let score = lockout.score_distribution.clone().unwrap_or_default();
+  let mut team_scores = vec![0; teams.iter().max().map_or(0, |team| team + 1)];
+  for (team, points) in teams.iter().zip(score.iter()) {
+    team_scores[*team] += points;
+  }
+  Some(team_scores)
+}
+
+/*
+  The teams sorted by their points, the same as `get_leaderboard_indices` but for teams. Teams
+  whose players all gave up are left out
+*/
+pub fn get_team_leaderboard(lockout: &Duel) -> Vec<(usize, u32)> {
+  let team_scores = get_team_scores(lockout).unwrap_or_default();
+  let teams = lockout.teams.clone().unwrap_or_default();
+  let mut leaderboard = team_scores
+    .into_iter()
+    .enumerate()
+    .filter(|(team, _)| teams.contains(team))
+    .collect::<Vec<_>>();
+  leaderboard.sort_by_key(|(_, points)| cmp::Reverse(*points));
+  leaderboard
+}
+
+// every player can solve every problem, nothing gets claimed
+pub fn is_scored_by_attempts(lockout: &Duel) -> bool {
+  lockout.scoring == Some(ScoringStyle::Icpc)
+}
+
 pub fn is_lockout_complete(lockout: &Duel) -> bool {
-  let indices = get_leaderboard_indices(&lockout);
-  let passed_time = lockout.begin_time.elapsed().unwrap();
-  if passed_time >= lockout.match_duration.unwrap() || indices.len() <= 1 {
+  if is_scored_by_attempts(lockout) {
+    return is_scored_lockout_complete(lockout);
+  }
+  let sorted_scores = if lockout.teams.is_some() {
+    get_team_leaderboard(lockout)
+      .into_iter()
+      .map(|(_, points)| points)
+      .collect::<Vec<_>>()
+  } else {
+    let score = lockout.score_distribution.clone().unwrap();
+    get_leaderboard_indices(lockout)
+      .into_iter()
+      .map(|index| score[index])
+      .collect::<Vec<_>>()
+  };
+  if lockout.get_time_left().is_zero() || sorted_scores.len() <= 1 {
     return true;
   }
 
-  let score = lockout.score_distribution.clone().unwrap();
-  let score_to_beat = score[indices[0]];
-  let mut current = score[indices[1]];
+  let score_to_beat = sorted_scores[0];
+  let mut current = sorted_scores[1];
   for point in lockout.problems_point.clone().unwrap() {
     current += point;
   }
@@ -191,16 +292,66 @@ pub fn is_lockout_complete(lockout: &Duel) -> bool {
   false
 }
 
+// without claims the lockout goes on until the time is up or everyone solved everything
+fn is_scored_lockout_complete(lockout: &Duel) -> bool {
+  let everyone_solved = lockout.attempts.as_ref().is_some_and(|attempts| {
+    attempts
+      .iter()
+      .all(|player| player.iter().all(|attempt| attempt.solved_at.is_some()))
+  });
+  lockout.get_time_left().is_zero() || lockout.players.len() <= 1 || everyone_solved
+}
+
+// the player who claimed every problem of the lockout
+pub fn get_sweeper(lockout: &Duel) -> Option<&User> {
+  let points = lockout.problems_point.as_ref()?;
+  let scores = lockout.score_distribution.as_ref()?;
+  if lockout.players.len() < 2 || points.iter().any(|point| *point != 0) {
+    return None;
+  }
+  let scorers = scores
+    .iter()
+    .enumerate()
+    .filter(|(_, score)| **score > 0)
+    .map(|(index, _)| index)
+    .collect::<Vec<_>>();
+  match scorers[..] {
+    [index] => lockout.players.get(index),
+    _ => None,
+  }
+}
+
+async fn finish_lockout(ctx: &Context, channel_id: ChannelId, lockout: Duel) {
+  if let Some(sweeper) = get_sweeper(&lockout) {
+    trigger_achievements(
+      ctx,
+      Some(channel_id),
+      &sweeper.userId,
+      BotEvent::LockoutSwept,
+    )
+    .await;
+  }
+  remove_lockout(ctx, lockout.players).await;
+}
+
+// the player who solved the problem first during the lockout and when they did
 async fn index_who_complete_problem(
   problem: &Problem,
   users: Vec<User>,
   user_submissions: &Vec<Vec<Submission>>,
-) -> Option<usize> {
+  begin: u64,
+  end: u64,
+) -> Option<(usize, u64)> {
   let mut index: Option<usize> = None;
   let mut current_time: u64 = 0;
   for i in 0..users.len() {
-    let parsed =
-      check_complete_problem_with_given_submission(&problem, user_submissions[i].clone()).await;
+    let parsed = check_complete_problem_with_given_submission(
+      problem,
+      user_submissions[i].clone(),
+      begin,
+      end,
+    )
+    .await;
     if let Ok(status) = parsed {
       if index == None {
         index = Some(i);
@@ -214,7 +365,15 @@ async fn index_who_complete_problem(
       }
     }
   }
-  index
+  Some((index?, current_time - begin))
+}
+
+// the unix times the lockout starts and ends at
+pub fn get_lockout_window(lockout: &Duel) -> (u64, u64) {
+  (
+    unix_seconds(lockout.begin_time),
+    unix_seconds(lockout.get_end_time()),
+  )
 }
 
 // return a vector that for each element is another vector contains all submissions of a user
@@ -233,24 +392,106 @@ pub async fn get_all_user_submissions(users: &Vec<User>) -> Vec<Vec<Submission>>
   user_submissions
 }
 
-async fn lockout_update(lockout: &mut Duel) {
+/*
+  The points the claimer of a problem gets. With `scoring=cf` they decay with the time since the
+  start, not counting pauses, and every rejected submission before the claim costs the penalty of
+  the lockout
+*/
+fn get_claim_points(lockout: &Duel, index: usize, point: u32, submissions: &[Submission]) -> u32 {
+  if lockout.scoring != Some(ScoringStyle::Codeforces) {
+    return point;
+  }
+  let (begin, end) = get_lockout_window(lockout);
+  let mut attempt = get_attempt(&lockout.problems[index], submissions, begin, end);
+  attempt.solved_at = attempt
+    .solved_at
+    .map(|solved_at| lockout.get_running_seconds(solved_at));
+  let wrong_penalty = lockout.wrong_penalty.unwrap_or_default() as f64;
+  decayed_points(point as f64, &attempt, wrong_penalty).map_or(point, |points| points as u32)
+}
+
+// the other players who solved problem `i` during the lockout, with when they did
+fn get_other_solves(
+  lockout: &Duel,
+  user_submissions: &[Vec<Submission>],
+  index: usize,
+  i: usize,
+) -> Vec<(String, u64)> {
+  let (begin, end) = get_lockout_window(lockout);
+  lockout
+    .players
+    .iter()
+    .zip(user_submissions.iter())
+    .enumerate()
+    .filter(|(other, _)| *other != index)
+    .filter_map(|(_, (player, submissions))| {
+      get_first_accepted(&lockout.problems[i], submissions, begin, end)
+        .map(|solved_at| (player.handle.clone(), solved_at))
+    })
+    .collect()
+}
+
+// update the standings and return the new solves that look suspicious
+async fn lockout_update(lockout: &mut Duel) -> Vec<SuspiciousSolve> {
+  let mut suspicious = Vec::new();
+  if is_scored_by_attempts(lockout) {
+    let user_submissions = get_all_user_submissions(&lockout.players).await;
+    let (begin, end) = get_lockout_window(lockout);
+    let attempts = get_attempts(&lockout.problems, &user_submissions, begin, end);
+    let previous = lockout.attempts.clone().unwrap_or_default();
+    for (index, player) in attempts.iter().enumerate() {
+      for (i, attempt) in player.iter().enumerate() {
+        let solved_before = previous
+          .get(index)
+          .and_then(|player| player.get(i))
+          .is_some_and(|attempt| attempt.solved_at.is_some());
+        if attempt.solved_at.is_none() || solved_before {
+          continue;
+        }
+        suspicious.extend(check_solve(
+          &lockout.players[index],
+          &lockout.problems[i],
+          &user_submissions[index],
+          begin,
+          end,
+          &get_other_solves(lockout, &user_submissions, index, i),
+        ));
+      }
+    }
+    lockout.attempts = Some(attempts);
+    return suspicious;
+  }
   let problems_point_cl = lockout.problems_point.clone().unwrap();
   let user_submissions = get_all_user_submissions(&lockout.players).await;
+  let (begin, end) = get_lockout_window(lockout);
   for (i, point) in problems_point_cl.iter().enumerate() {
     if *point == 0 {
       continue;
     }
-    if let Some(index) = index_who_complete_problem(
+    if let Some((index, solved_at)) = index_who_complete_problem(
       &lockout.problems[i],
       lockout.players.clone(),
       &user_submissions,
+      begin,
+      end,
     )
     .await
     {
-      lockout.add_score(index, *point);
+      let points = get_claim_points(lockout, i, *point, &user_submissions[index]);
+      lockout.add_score(index, points);
       lockout.set_point(i);
+      lockout.set_claim(i, points, solved_at);
+      suspicious.extend(check_solve(
+        &lockout.players[index],
+        &lockout.problems[i],
+        &user_submissions[index],
+        begin,
+        end,
+        &get_other_solves(lockout, &user_submissions, index, i),
+      ));
     }
   }
+  suspicious
 }
 
 pub async fn single_lockout_interactor(ctx: &Context, mut lockout: Duel) {
@@ -268,22 +509,40 @@ pub async fn single_lockout_interactor(ctx: &Context, mut lockout: Duel) {
     };
   }
 
-  let passed_time = lockout.begin_time.elapsed().unwrap();
   let ctx_1 = ctx.clone();
   let msg_1 = msg.clone();
   tokio::spawn(async move {
-    if passed_time >= lockout.match_duration.unwrap() {
+    if lockout.get_time_left().is_zero() && !lockout.is_paused() {
+      if is_scored_by_attempts(&lockout) {
+        let suspicious = lockout_update(&mut lockout).await;
+        report_suspicious_solves(&ctx_1, &msg_1, suspicious).await;
+      }
       standings!(ctx_1, msg_1, lockout, true);
-      remove_lockout(&ctx_1, lockout.players).await;
+      finish_lockout(&ctx_1, msg_1.channel_id, lockout).await;
       return;
     }
 
-    let mut message_collector = MessageCollector::new(&ctx_1.shard)
-      .timeout(lockout.match_duration.unwrap() - passed_time)
-      .stream();
+    let mut votes = MatchVotes::default();
+    let mut message_collector = MessageCollector::new(&ctx_1.shard).stream();
 
     loop {
-      if let Some(message) = message_collector.next().await {
+      if let Some(message) = next_match_message(&lockout, &mut message_collector).await {
+        if is_match_control(&message.content) {
+          let voters = lockout
+            .players
+            .iter()
+            .map(|player| player.userId.clone())
+            .collect::<Vec<_>>();
+          if !voters.contains(&message.author.id.to_string()) {
+            continue;
+          }
+          if handle_match_control(&ctx_1, &mut lockout, &mut votes, &message, &voters).await {
+            standings!(ctx_1, msg_1, lockout, true);
+            remove_lockout(&ctx_1, lockout.players).await;
+            return;
+          }
+          continue;
+        }
         if message.content != format!("~match update")
           && message.content != format!("~match giveup")
         {
@@ -307,7 +566,7 @@ pub async fn single_lockout_interactor(ctx: &Context, mut lockout: Duel) {
           continue;
         }
         if message.content == format!("~match giveup") {
-          lockout.remove_user(msg.author.id.to_string());
+          lockout.remove_user(user.userId.clone());
         }
         if message.content == format!("~match update")
           || message.content == format!("~match giveup")
@@ -318,10 +577,13 @@ pub async fn single_lockout_interactor(ctx: &Context, mut lockout: Duel) {
             .send_message(&ctx_1.http, builder)
             .await
             .unwrap();
-          lockout_update(&mut lockout).await;
+          let suspicious = lockout_update(&mut lockout).await;
+          report_suspicious_solves(&ctx_1, &msg_1, suspicious).await;
+          // spectators watch the saved state
+          save_duel(&ctx_1, &lockout).await;
           if is_lockout_complete(&lockout) {
             edit_standings!(ctx_1, message, lockout, true);
-            remove_lockout(&ctx_1, lockout.players).await;
+            finish_lockout(&ctx_1, msg_1.channel_id, lockout).await;
             return;
           } else {
             edit_standings!(ctx_1, message, lockout, true);
@@ -331,8 +593,12 @@ pub async fn single_lockout_interactor(ctx: &Context, mut lockout: Duel) {
         break;
       }
     }
+    if is_scored_by_attempts(&lockout) {
+      let suspicious = lockout_update(&mut lockout).await;
+      report_suspicious_solves(&ctx_1, &msg_1, suspicious).await;
+    }
     standings!(ctx_1, msg_1, lockout, true);
-    remove_lockout(&ctx_1, lockout.players).await;
+    finish_lockout(&ctx_1, msg_1.channel_id, lockout).await;
     return;
   });
 }
@@ -351,6 +617,88 @@ pub async fn lockout_interactor(ctx: &Context) {
   }
 }
 
+// split the `scoring=...` and `penalty=...` arguments from the other arguments of a command
+pub fn take_scoring_arg(args: &Args) -> Result<(Args, Option<ScoringStyle>, Option<u32>), String> {
+  let mut scoring = None;
+  let mut penalty = None;
+  let mut rest = Vec::new();
+  for arg in args.raw() {
+    if let Some(name) = arg.strip_prefix("scoring=") {
+      scoring = Some(ScoringStyle::parse(name)?);
+    } else if let Some(points) = arg.strip_prefix("penalty=") {
+      match points.parse::<u32>() {
+        Ok(points) => penalty = Some(points),
+        Err(_) => return Err(format!("`{points}` is not a penalty")),
+      }
+    } else {
+      rest.push(arg);
+    }
+  }
+  if penalty.is_some() && scoring != Some(ScoringStyle::Codeforces) {
+    return Err("`penalty=` only works with `scoring=cf`".to_owned());
+  }
+  Ok((
+    Args::new(&rest.join(" "), &[Delimiter::Single(' ')]),
+    scoring,
+    penalty,
+  ))
+}
+
+/*
+  Split the `vs` separators from the other arguments. Every group of mentions is a team and the
+  first one is the team of the sender, no `vs` means everyone plays alone
+*/
+pub fn take_teams_arg(args: &Args) -> (Args, Vec<Vec<UserId>>) {
+  let mut teams: Vec<Vec<UserId>> = vec![Vec::new()];
+  let mut rest = Vec::new();
+  for arg in args.raw() {
+    if arg == "vs" {
+      teams.push(Vec::new());
+      continue;
+    }
+    if let Some(user_id) = extract_user_id(arg.to_owned()) {
+      teams.last_mut().unwrap().push(user_id);
+    }
+    rest.push(arg);
+  }
+  if teams.len() == 1 {
+    teams.clear();
+  }
+  (Args::new(&rest.join(" "), &[Delimiter::Single(' ')]), teams)
+}
+
+// the team of every user who joined, numbered from 0 and skipping the teams nobody joined
+pub fn assign_teams(users: &[User], teams: &[Vec<UserId>]) -> Result<Option<Vec<usize>>, String> {
+  if teams.is_empty() {
+    return Ok(None);
+  }
+  let mut assigned = Vec::new();
+  for (index, user) in users.iter().enumerate() {
+    let team = match teams
+      .iter()
+      .position(|team| team.iter().any(|id| id.to_string() == user.userId))
+    {
+      Some(team) => team,
+      // the sender is in the first team without mentioning themselves
+      None if index == 0 => 0,
+      None => return Err(format!("<@{}> isn't in any team", user.userId)),
+    };
+    assigned.push(team);
+  }
+  let mut joined = assigned.clone();
+  joined.sort();
+  joined.dedup();
+  if joined.len() < 2 {
+    return Err("Only one team has players left".to_owned());
+  }
+  Ok(Some(
+    assigned
+      .iter()
+      .map(|team| joined.iter().position(|current| current == team).unwrap())
+      .collect(),
+  ))
+}
+
 fn to_num(arg: Option<&str>) -> Option<i32> {
   match arg {
     Some(parsed) => {
@@ -386,6 +734,31 @@ pub async fn lockout(ctx: &Context, msg: &Message, args: Args) -> CommandResult
     msg.channel_id.send_message(&ctx.http, message).await?;
     return Ok(());
   }
+  let (args, requested_pool) = match take_pool_arg(&args) {
+    Ok(parsed) => parsed,
+    Err(why) => {
+      error_response!(ctx, msg, why);
+      return Ok(());
+    }
+  };
+  let pool = get_problem_pool(ctx, msg.guild_id, requested_pool).await;
+  let (args, scoring, wrong_penalty) = match take_scoring_arg(&args) {
+    Ok(parsed) => parsed,
+    Err(why) => {
+      error_response!(ctx, msg, why);
+      return Ok(());
+    }
+  };
+  let (args, teams) = take_teams_arg(&args);
+  let (args, filter) = take_fair_arg(&args);
+  if scoring == Some(ScoringStyle::Icpc) && !teams.is_empty() {
+    error_response!(
+      ctx,
+      msg,
+      "Team lockouts can't be scored with `scoring=icpc`".to_owned()
+    );
+    return Ok(());
+  }
   let args_result = handle_args(
     &ctx,
     &msg,
@@ -498,6 +871,13 @@ pub async fn lockout(ctx: &Context, msg: &Message, args: Args) -> CommandResult
       error_response!(ctx, msg, format!("No one can join with you :("));
       return Ok(());
     }
+    let teams = match assign_teams(&users_in_lockout, &teams) {
+      Ok(teams) => teams,
+      Err(why) => {
+        error_response!(ctx, msg, why);
+        return Ok(());
+      }
+    };
 
     let parsed_rating: u32 = match lockout_rating {
       -1 => {
@@ -525,10 +905,15 @@ pub async fn lockout(ctx: &Context, msg: &Message, args: Args) -> CommandResult
       &ctx,
       &msg,
       users_in_lockout,
+      teams,
+      scoring,
+      wrong_penalty,
       number_of_problems as u32,
       lockout_duration,
       parsed_rating,
       lockout_problems_increment as u32,
+      &pool,
+      &filter,
     )
     .await;
   } else {
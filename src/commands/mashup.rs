@@ -0,0 +1,468 @@
+use std::time::SystemTime;
+
+use serenity::builder::{CreateEmbed, CreateEmbedFooter, CreateMessage};
+use serenity::collector::MessageCollector;
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::{Args, CommandResult, Delimiter};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use tokio::time::Duration;
+
+use crate::commands::duel::*;
+use crate::commands::giveme::*;
+use crate::commands::handle::*;
+use crate::commands::lockout::*;
+use crate::commands::match_control::*;
+
+use crate::core::data::*;
+use crate::core::scoring::*;
+use crate::error_response;
+use crate::utils::message_creator::*;
+
+const WAIT_DURATION: Duration = Duration::from_millis(1000 * 30);
+// minutes
+const MIN_MASHUP_DURATION: u64 = 30;
+const MAX_MASHUP_DURATION: u64 = 60 * 5;
+
+async fn show_help() -> CreateMessage {
+  let embed = CreateEmbed::new()
+    .title("Usage of `mashup`")
+    .description(format!(
+      "`~mashup icpc <number of problems> <duration in minutes> <@user>` (a timed team contest on icpc gym problems nobody in the team has solved, up to {MAX_ICPC_PROBLEM_REQUEST} problems and {MIN_MASHUP_DURATION} to {MAX_MASHUP_DURATION} minutes, mention nobody to practice alone)\n
+      `~mashup stats [@user]` (what someone contributed in the past mashups)\n
+      During the mashup type `~match update` to see the scoreboard or `~match giveup` to stop, what you solved still counts for the team\n
+      `~match draw` / `~match extend <minutes>` (ends the match or adds time once every player asked for it)\n
+      `~match pause` / `~match resume` (freeze the clock, e.g. while Codeforces is down)"
+    ))
+    .color(Colour::DARK_GREEN);
+  CreateMessage::new().embed(embed)
+}
+
+/*
+  The attempts of the whole team, where the submissions of every member count as the team's,
+  and the attempts of each member on their own
+*/
+pub fn get_team_attempts(
+  duel: &Duel,
+  user_submissions: &[Vec<Submission>],
+) -> (Vec<ProblemAttempt>, Vec<Vec<ProblemAttempt>>) {
+  let (begin, end) = get_lockout_window(duel);
+  let team_submissions = user_submissions.concat();
+  let mut team = duel
+    .problems
+    .iter()
+    .map(|problem| get_attempt(problem, &team_submissions, begin, end))
+    .collect::<Vec<_>>();
+  let mut members = user_submissions
+    .iter()
+    .map(|submissions| {
+      duel
+        .problems
+        .iter()
+        .map(|problem| get_attempt(problem, submissions, begin, end))
+        .collect::<Vec<_>>()
+    })
+    .collect::<Vec<_>>();
+  // the time spent paused doesn't add to the penalty
+  for attempt in team.iter_mut().chain(members.iter_mut().flatten()) {
+    attempt.solved_at = attempt
+      .solved_at
+      .map(|solved_at| duel.get_running_seconds(solved_at));
+  }
+  (team, members)
+}
+
+// a problem is credited to the member whose accepted submission solved it for the team
+pub fn get_contributions(
+  duel: &Duel,
+  team: &[ProblemAttempt],
+  members: &[Vec<ProblemAttempt>],
+) -> Vec<PlayerResult> {
+  let penalty = get_score(team, &vec![0f64; team.len()]).penalty;
+  duel
+    .players
+    .iter()
+    .zip(members.iter())
+    .map(|(player, attempts)| PlayerResult {
+      user_id: player.userId.clone(),
+      handle: player.handle.clone(),
+      rank: 1,
+      solved: duel
+        .problems
+        .iter()
+        .zip(team.iter().zip(attempts.iter()))
+        .filter(|(_, (team, own))| team.solved_at.is_some() && team.solved_at == own.solved_at)
+        .map(|(problem, _)| problem.index.clone())
+        .collect(),
+      wrong_attempts: attempts.iter().map(|attempt| attempt.wrong_attempts).sum(),
+      penalty,
+    })
+    .collect()
+}
+
+fn create_mashup_embed(
+  duel: &Duel,
+  team: &[ProblemAttempt],
+  contributions: &[PlayerResult],
+) -> CreateEmbed {
+  let score = get_score(team, &vec![0f64; team.len()]);
+  let cells = duel
+    .problems
+    .iter()
+    .zip(team.iter())
+    .filter_map(|(problem, attempt)| {
+      get_attempt_cell(&problem.index, attempt, ScoringStyle::Icpc, 0f64)
+    })
+    .collect::<Vec<_>>();
+  let members = contributions
+    .iter()
+    .map(|result| {
+      format!(
+        "<@{user_id}> solved {solved}, {wrong} wrong attempt(s){gave_up}",
+        user_id = result.user_id,
+        gave_up = if duel.gave_up.contains(&result.user_id) {
+          " (gave up)"
+        } else {
+          ""
+        },
+        solved = if result.solved.is_empty() {
+          "nothing".to_owned()
+        } else {
+          result.solved.join(" ")
+        },
+        wrong = result.wrong_attempts
+      )
+    })
+    .collect::<Vec<_>>();
+  let problems = duel
+    .problems
+    .iter()
+    .map(|problem| {
+      create_href(
+        problem_url(problem),
+        &format!("{}. {}", problem.index, problem.name),
+      )
+    })
+    .collect::<Vec<_>>();
+  let time_left = get_match_time_left_string(duel);
+  CreateEmbed::new()
+    .title("ICPC mashup")
+    .description(format!(
+      "**{solved}** solved, {penalty} penalty\n{cells}",
+      solved = score.solved,
+      penalty = score.penalty,
+      cells = if cells.is_empty() {
+        "No attempt yet".to_owned()
+      } else {
+        cells.join(" | ")
+      }
+    ))
+    .field("Members", members.join("\n"), false)
+    .field("Problems", problems.join("\n"), false)
+    .colour(if time_left == "Ended" {
+      Colour::GOLD
+    } else {
+      Colour::TEAL
+    })
+    .footer(CreateEmbedFooter::new(&time_left))
+}
+
+async fn send_scoreboard(ctx: &Context, channel_id: ChannelId, duel: &Duel) -> Vec<PlayerResult> {
+  let builder = create_await_message();
+  let message = match channel_id.send_message(&ctx.http, builder).await {
+    Ok(message) => message,
+    Err(_) => return Vec::new(),
+  };
+  let user_submissions = get_all_user_submissions(&duel.players).await;
+  let (team, members) = get_team_attempts(duel, &user_submissions);
+  let contributions = get_contributions(duel, &team, &members);
+  edit_to_message(
+    ctx,
+    create_mashup_embed(duel, &team, &contributions),
+    message,
+  )
+  .await;
+  contributions
+}
+
+pub async fn single_mashup_interactor(ctx: &Context, mut duel: Duel) {
+  let msg = duel.channel_id.clone();
+  let ctx_1 = ctx.clone();
+  tokio::spawn(async move {
+    let mut votes = MatchVotes::default();
+    let mut message_collector = MessageCollector::new(&ctx_1.shard)
+      .channel_id(msg.channel_id)
+      .stream();
+    // the clock only runs out while the mashup isn't paused
+    while let Some(message) = next_match_message(&duel, &mut message_collector).await {
+      let user_id = message.author.id.to_string();
+      if !duel.players.iter().any(|player| player.userId == user_id) {
+        continue;
+      }
+      let gave_up = duel.gave_up.contains(&user_id);
+      if is_match_control(&message.content) && !gave_up {
+        let voters = duel
+          .players
+          .iter()
+          .map(|player| player.userId.clone())
+          .filter(|player| !duel.gave_up.contains(player))
+          .collect::<Vec<_>>();
+        if handle_match_control(&ctx_1, &mut duel, &mut votes, &message, &voters).await {
+          break;
+        }
+        continue;
+      }
+      if message.content != "~match update" && message.content != "~match giveup" {
+        continue;
+      }
+      if message.content == "~match giveup" {
+        if gave_up {
+          continue;
+        }
+        // the member stays in the team for the tally, the last one giving up ends the mashup
+        duel.gave_up.push(user_id.clone());
+        if duel.gave_up.len() == duel.players.len() {
+          break;
+        }
+        save_duel(&ctx_1, &duel).await;
+        edit_data(&ctx_1, |data| {
+          if let Some(user) = data.data.iter_mut().find(|user| user.userId == user_id) {
+            user.duel_id = None;
+          }
+        })
+        .await;
+        continue;
+      }
+      send_scoreboard(&ctx_1, msg.channel_id, &duel).await;
+    }
+    let results = send_scoreboard(&ctx_1, msg.channel_id, &duel).await;
+    archive_match(
+      &ctx_1,
+      MatchRecord {
+        duel_type: DuelType::MASHUP,
+        channel_id: msg.channel_id,
+        begin_time: duel.begin_time,
+        end_time: SystemTime::now().min(duel.get_end_time()),
+        problems: duel.problems.clone(),
+        results,
+      },
+    )
+    .await;
+    remove_duel(&ctx_1, duel.players).await;
+  });
+}
+
+pub async fn mashup_interactor(ctx: &Context) {
+  let duels = match get_duels(ctx).await {
+    Some(duels) => duels,
+    None => return,
+  };
+  for duel in duels.into_iter() {
+    if duel.duel_type == DuelType::MASHUP {
+      single_mashup_interactor(ctx, duel).await;
+    }
+  }
+}
+
+// what `user_id` did over every archived mashup
+fn create_stats_embed(user_id: &String, history: &[MatchRecord]) -> CreateEmbed {
+  let mut played = 0;
+  let mut credited = 0;
+  let mut team_solved = 0;
+  let mut wrong_attempts = 0;
+  for record in history
+    .iter()
+    .filter(|record| record.duel_type == DuelType::MASHUP)
+  {
+    let own = match record
+      .results
+      .iter()
+      .find(|result| &result.user_id == user_id)
+    {
+      Some(own) => own,
+      None => continue,
+    };
+    played += 1;
+    credited += own.solved.len();
+    team_solved += record
+      .results
+      .iter()
+      .map(|result| result.solved.len())
+      .sum::<usize>();
+    wrong_attempts += own.wrong_attempts;
+  }
+  let share = if team_solved == 0 {
+    0f64
+  } else {
+    credited as f64 * 100f64 / team_solved as f64
+  };
+  CreateEmbed::new()
+    .title("Mashup stats")
+    .description(format!("<@{user_id}>"))
+    .field("Mashups", played.to_string(), true)
+    .field("Problems solved", credited.to_string(), true)
+    .field("Share of the team", format!("{share:.0}%"), true)
+    .field("Wrong attempts", wrong_attempts.to_string(), true)
+    .colour(Colour::BLUE)
+}
+
+async fn show_stats(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+  let user_id = match args.single::<String>() {
+    Ok(mention) => match extract_user_id(mention) {
+      Some(user_id) => user_id.to_string(),
+      None => {
+        error_response!(ctx, msg, "Please mention a user".to_owned());
+        return Ok(());
+      }
+    },
+    Err(_) => msg.author.id.to_string(),
+  };
+  let history = match get_data(ctx).await {
+    Ok(data) => data.match_history,
+    Err(why) => {
+      error_response!(ctx, msg, why);
+      return Ok(());
+    }
+  };
+  let message = CreateMessage::new().embed(create_stats_embed(&user_id, &history));
+  msg.channel_id.send_message(&ctx.http, message).await?;
+  Ok(())
+}
+
+#[command]
+pub async fn mashup(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+  match args.single::<String>().unwrap_or_default().as_str() {
+    "icpc" => (),
+    "stats" => return show_stats(ctx, msg, args).await,
+    _ => {
+      msg
+        .channel_id
+        .send_message(&ctx.http, show_help().await)
+        .await?;
+      return Ok(());
+    }
+  }
+  let problem_count = match args.single::<u32>() {
+    Ok(count) if count > 0 && count <= MAX_ICPC_PROBLEM_REQUEST as u32 => count,
+    _ => {
+      error_response!(
+        ctx,
+        msg,
+        format!("Please provide the number of problems (1 to {MAX_ICPC_PROBLEM_REQUEST})")
+      );
+      return Ok(());
+    }
+  };
+  let duration = match args.single::<u64>() {
+    Ok(minutes) if (MIN_MASHUP_DURATION..=MAX_MASHUP_DURATION).contains(&minutes) => {
+      Duration::from_secs(minutes * 60)
+    }
+    _ => {
+      error_response!(
+        ctx,
+        msg,
+        format!(
+          "Please provide the duration in minutes ({MIN_MASHUP_DURATION} to {MAX_MASHUP_DURATION})"
+        )
+      );
+      return Ok(());
+    }
+  };
+  let rest = Args::new(args.rest(), &[Delimiter::Single(' ')]);
+  let teammates = match handle_args(
+    ctx,
+    msg,
+    rest,
+    "You are already in the team".to_owned(),
+    false,
+  )
+  .await
+  {
+    Ok((teammates, _)) => teammates,
+    Err(why) => {
+      error_response!(ctx, msg, why);
+      return Ok(());
+    }
+  };
+
+  let accepted_users = if teammates.is_empty() {
+    Vec::new()
+  } else {
+    msg
+      .channel_id
+      .say(
+        &ctx.http,
+        format!(
+          "<@{user}> invited some users to a mashup team\n
+      if you want to join, please reponse with ~accept <@{user}> within 30 seconds",
+          user = msg.author.id
+        ),
+      )
+      .await?;
+    collect_messages(ctx, msg, &teammates, WAIT_DURATION).await
+  };
+  let users = confirm_user_in_match(ctx, msg, accepted_users).await;
+  if users.is_empty() {
+    return Ok(());
+  }
+
+  let builder = create_await_message();
+  let message = msg.channel_id.send_message(&ctx.http, builder).await?;
+  let handles = users
+    .iter()
+    .map(|user| user.handle.clone())
+    .collect::<Vec<_>>();
+  let problems = match get_icpc_problems(problem_count, &handles).await {
+    Ok(problems) => problems,
+    Err(why) => {
+      edit_to_failed_status(ctx, message).await;
+      error_response!(ctx, msg, why);
+      return Ok(());
+    }
+  };
+  let duel = create_match(
+    ctx,
+    Duel {
+      duel_id: 0,
+      players: users,
+      begin_time: SystemTime::now(),
+      problems,
+      channel_id: msg.clone(),
+      duel_type: DuelType::MASHUP,
+      score_distribution: None,
+      match_duration: Some(duration),
+      problems_point: None,
+      contest: None,
+      scoring: Some(ScoringStyle::Icpc),
+      teams: None,
+      tournament_id: None,
+      attempts: None,
+      wrong_penalty: None,
+      claimed_points: None,
+      claimed_at: None,
+      gave_up: Vec::new(),
+      end_time: None,
+      paused_at: None,
+      pauses: Vec::new(),
+    },
+  )
+  .await;
+  let team = vec![ProblemAttempt::default(); duel.problems.len()];
+  let contributions = get_contributions(&duel, &team, &vec![team.clone(); duel.players.len()]);
+  edit_to_message(
+    ctx,
+    create_mashup_embed(&duel, &team, &contributions),
+    message,
+  )
+  .await;
+  msg
+    .channel_id
+    .say(
+      &ctx.http,
+      "The mashup has started! Type `~match update` to see the scoreboard",
+    )
+    .await?;
+  single_mashup_interactor(ctx, duel).await;
+  Ok(())
+}
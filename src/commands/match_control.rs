@@ -0,0 +1,397 @@
+use std::collections::HashSet;
+
+use serenity::builder::{CreateEmbed, CreateEmbedFooter, CreateMessage, EditMessage};
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::{Args, CommandResult};
+use serenity::futures::{Stream, StreamExt};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use tokio::time::Duration;
+
+use crate::commands::duel::{get_duel_attempt, is_playing};
+use crate::core::data::*;
+use crate::error_response;
+use crate::utils::message_creator::*;
+
+const MAX_EXTENSION_MINUTES: u64 = 120;
+const PAUSED_WAIT: Duration = Duration::from_secs(60);
+const WATCH_REFRESH: Duration = Duration::from_secs(30);
+const CONTROLS: [&str; 4] = [
+  "~match draw",
+  "~match extend",
+  "~match pause",
+  "~match resume",
+];
+
+// the draw and the extension asked for so far, they go through once every player agreed
+#[derive(Default)]
+pub struct MatchVotes {
+  draw: HashSet<String>,
+  extension: Option<(u64, HashSet<String>)>,
+}
+
+pub fn is_match_control(content: &str) -> bool {
+  CONTROLS
+    .iter()
+    .any(|control| content.trim().starts_with(control))
+}
+
+/*
+  The next message sent while the match runs, None once its time is up. The clock is frozen while
+  the match is paused, so it keeps waiting until someone resumes it
+*/
+pub async fn next_match_message<S>(duel: &Duel, messages: &mut S) -> Option<Message>
+where
+  S: Stream<Item = Message> + Unpin,
+{
+  loop {
+    let wait = if duel.is_paused() {
+      PAUSED_WAIT
+    } else {
+      duel.get_time_left()
+    };
+    match tokio::time::timeout(wait, messages.next()).await {
+      Ok(message) => return message,
+      Err(_) if duel.is_paused() => continue,
+      Err(_) => return None,
+    }
+  }
+}
+
+async fn announce(ctx: &Context, channel_id: ChannelId, description: String) {
+  let embed = CreateEmbed::new()
+    .description(description)
+    .colour(Colour::BLUE)
+    .timestamp(Timestamp::now());
+  let _ = channel_id
+    .send_message(&ctx.http, CreateMessage::new().embed(embed))
+    .await;
+}
+
+/*
+  Handle `~match draw`, `~match extend <minutes>`, `~match pause` and `~match resume` sent by one
+  of the `voters`, the players still in the match. Pausing and resuming is up to any of them, a
+  draw or an extension needs everyone. Returns true once everyone agreed to a draw
+*/
+pub async fn handle_match_control(
+  ctx: &Context,
+  duel: &mut Duel,
+  votes: &mut MatchVotes,
+  message: &Message,
+  voters: &[String],
+) -> bool {
+  let channel_id = message.channel_id;
+  let user_id = message.author.id.to_string();
+  let mut args = message.content.split_whitespace().skip(1);
+  match args.next().unwrap_or_default() {
+    "draw" => {
+      votes.draw.insert(user_id.clone());
+      let agreed = voters
+        .iter()
+        .filter(|voter| votes.draw.contains(*voter))
+        .count();
+      if agreed == voters.len() {
+        announce(ctx, channel_id, "Everyone agreed to a draw".to_owned()).await;
+        return true;
+      }
+      announce(
+        ctx,
+        channel_id,
+        format!(
+          "<@{user_id}> offers a draw ({agreed}/{total}), type `~match draw` to agree",
+          total = voters.len()
+        ),
+      )
+      .await;
+    }
+    "extend" => {
+      let minutes = match args
+        .next()
+        .and_then(|minutes| minutes.parse::<u64>().ok())
+        .filter(|minutes| (1..=MAX_EXTENSION_MINUTES).contains(minutes))
+      {
+        Some(minutes) => minutes,
+        None => {
+          error_response!(
+            ctx,
+            message,
+            format!("Please provide between 1 and {MAX_EXTENSION_MINUTES} minutes")
+          );
+          return false;
+        }
+      };
+      match &mut votes.extension {
+        Some((asked, agreed)) if *asked == minutes => {
+          agreed.insert(user_id.clone());
+        }
+        _ => votes.extension = Some((minutes, HashSet::from([user_id.clone()]))),
+      }
+      let agreed = votes.extension.as_ref().map_or(0, |(_, agreed)| {
+        voters
+          .iter()
+          .filter(|voter| agreed.contains(*voter))
+          .count()
+      });
+      if agreed < voters.len() {
+        announce(
+          ctx,
+          channel_id,
+          format!(
+            "<@{user_id}> asks for {minutes} more minute(s) ({agreed}/{total}), type `~match extend {minutes}` to agree",
+            total = voters.len()
+          ),
+        )
+        .await;
+        return false;
+      }
+      votes.extension = None;
+      duel.extend(Duration::from_secs(minutes * 60));
+      save_duel(ctx, duel).await;
+      announce(
+        ctx,
+        channel_id,
+        format!(
+          "The match is extended by {minutes} minute(s). {}",
+          get_match_time_left_string(duel)
+        ),
+      )
+      .await;
+    }
+    "pause" => {
+      if duel.is_paused() {
+        error_response!(ctx, message, "The match is already paused".to_owned());
+        return false;
+      }
+      duel.pause();
+      save_duel(ctx, duel).await;
+      announce(
+        ctx,
+        channel_id,
+        format!("<@{user_id}> paused the match, the clock is frozen until `~match resume`"),
+      )
+      .await;
+    }
+    "resume" => {
+      if !duel.is_paused() {
+        error_response!(ctx, message, "The match isn't paused".to_owned());
+        return false;
+      }
+      duel.resume();
+      save_duel(ctx, duel).await;
+      announce(
+        ctx,
+        channel_id,
+        format!(
+          "<@{user_id}> resumed the match. {}",
+          get_match_time_left_string(duel)
+        ),
+      )
+      .await;
+    }
+    _ => {}
+  }
+  false
+}
+
+async fn show_help() -> CreateMessage {
+  let embed = CreateEmbed::new()
+    .title("Usage of `match`")
+    .description(
+      "`~match list` (the duels and lockouts running on the server)\n
+      `~match watch <id>` (follow a duel or a lockout, the standings refresh on their own)",
+    )
+    .color(Colour::DARK_GREEN);
+  CreateMessage::new().embed(embed)
+}
+
+// the duels and lockouts running on the guild, the other matches have their own scoreboards
+async fn get_running_matches(ctx: &Context, guild_id: Option<GuildId>) -> Vec<Duel> {
+  get_duels(ctx)
+    .await
+    .unwrap_or_default()
+    .into_iter()
+    .filter(|duel| {
+      duel.channel_id.guild_id == guild_id
+        && (duel.duel_type == DuelType::DUEL || duel.duel_type == DuelType::LOCKOUT)
+    })
+    .collect()
+}
+
+fn create_match_list_message(matches: &[Duel]) -> CreateMessage {
+  let description = if matches.is_empty() {
+    "There is no running duel or lockout on the server".to_owned()
+  } else {
+    matches
+      .iter()
+      .map(|duel| {
+        let players = duel
+          .players
+          .iter()
+          .map(|player| format!("<@{}>", player.userId))
+          .collect::<Vec<_>>()
+          .join(", ");
+        format!(
+          "`#{id}` {kind} in <#{channel}>: {players}\n{time_left}",
+          id = duel.duel_id,
+          kind = if duel.duel_type == DuelType::DUEL {
+            "Duel"
+          } else {
+            "Lockout"
+          },
+          channel = duel.channel_id.channel_id,
+          time_left = get_match_time_left_string(duel)
+        )
+      })
+      .collect::<Vec<_>>()
+      .join("\n\n")
+  };
+  let embed = CreateEmbed::new()
+    .title("Running matches")
+    .description(description)
+    .footer(CreateEmbedFooter::new(
+      "Type `~match watch <id>` to follow one",
+    ))
+    .colour(Colour::TEAL)
+    .timestamp(Timestamp::now());
+  CreateMessage::new().embed(embed)
+}
+
+fn create_duel_spectator_embed(duel: &Duel, show_problem: bool) -> CreateEmbed {
+  let players = (0..duel.players.len())
+    .map(|index| {
+      let player = &duel.players[index];
+      let status = match get_duel_attempt(duel, index).solved_at {
+        Some(solved_at) => format!("solved at {}", format_match_time(solved_at)),
+        None if is_playing(duel, index) => "playing".to_owned(),
+        None => "gave up".to_owned(),
+      };
+      format!("<@{}> {status}", player.userId)
+    })
+    .collect::<Vec<_>>()
+    .join("\n");
+  let mut embed = CreateEmbed::new()
+    .title(format!("Duel #{}", duel.duel_id))
+    .field("Players", players, false);
+  if let Some(problem) = duel.problems.first() {
+    let rating = problem.difficulty_string().unwrap_or_default();
+    let shown = if show_problem {
+      create_href(problem_url(problem), &problem.name)
+    } else {
+      "Hidden until the duel ends".to_owned()
+    };
+    embed = embed.field("Problem", format!("{shown} ({rating})"), false);
+  }
+  embed
+    .colour(Colour::TEAL)
+    .footer(CreateEmbedFooter::new(get_match_time_left_string(duel)))
+}
+
+// the lockout embed of the players, or the duel, for the people who only watch
+fn create_spectator_embed(duel: &Duel, show_problems: bool) -> CreateEmbed {
+  match duel.duel_type {
+    DuelType::DUEL => create_duel_spectator_embed(duel, show_problems),
+    _ => create_lockout_status_embed(duel, show_problems),
+  }
+}
+
+/*
+  Post the spectator embed of the match and refresh it from the saved state until the match is
+  over, the last state is then shown with its problems
+*/
+async fn watch_match(ctx: &Context, msg: &Message, duel: Duel) {
+  let hide_problems = get_guild_config(ctx, msg.guild_id)
+    .await
+    .hide_spectator_problems;
+  let builder = CreateMessage::new().embed(create_spectator_embed(&duel, !hide_problems));
+  let mut message = match msg.channel_id.send_message(&ctx.http, builder).await {
+    Ok(message) => message,
+    Err(_) => return,
+  };
+  let ctx = ctx.clone();
+  tokio::spawn(async move {
+    let mut last_state = duel;
+    loop {
+      tokio::time::sleep(WATCH_REFRESH).await;
+      // ids are reused, a match with another start or channel is a new one
+      let current = get_duel(&ctx, last_state.duel_id).await.filter(|duel| {
+        duel.begin_time == last_state.begin_time
+          && duel.channel_id.channel_id == last_state.channel_id.channel_id
+      });
+      let embed = match current {
+        Some(duel) => {
+          last_state = duel;
+          create_spectator_embed(&last_state, !hide_problems)
+        }
+        None => {
+          let embed = create_spectator_embed(&last_state, true)
+            .colour(Colour::GOLD)
+            .footer(CreateEmbedFooter::new("Ended"));
+          let _ = message.edit(&ctx, EditMessage::new().embed(embed)).await;
+          return;
+        }
+      };
+      let _ = message.edit(&ctx, EditMessage::new().embed(embed)).await;
+    }
+  });
+}
+
+#[command("match")]
+#[only_in(guilds)]
+pub async fn match_command(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+  let option = match args.single::<String>() {
+    Ok(option) => option,
+    Err(_) => {
+      msg
+        .channel_id
+        .send_message(&ctx.http, show_help().await)
+        .await?;
+      return Ok(());
+    }
+  };
+  match option.as_str() {
+    "list" => {
+      let matches = get_running_matches(ctx, msg.guild_id).await;
+      msg
+        .channel_id
+        .send_message(&ctx.http, create_match_list_message(&matches))
+        .await?;
+    }
+    "watch" => {
+      let id = match args.single::<usize>() {
+        Ok(id) => id,
+        Err(_) => {
+          error_response!(
+            ctx,
+            msg,
+            "Please provide the id of the match, see `~match list`".to_owned()
+          );
+          return Ok(());
+        }
+      };
+      match get_running_matches(ctx, msg.guild_id)
+        .await
+        .into_iter()
+        .find(|duel| duel.duel_id == id)
+      {
+        Some(duel) => watch_match(ctx, msg, duel).await,
+        None => {
+          error_response!(
+            ctx,
+            msg,
+            format!("There is no running duel or lockout `#{id}` on the server")
+          );
+        }
+      }
+    }
+    "help" | "h" => {
+      msg
+        .channel_id
+        .send_message(&ctx.http, show_help().await)
+        .await?;
+    }
+    // the other options are read by the matches themselves
+    _ => {}
+  }
+  Ok(())
+}
@@ -7,4 +7,18 @@ pub mod giveme;
 pub mod help;
 pub mod latency;
 pub mod duel;
-pub mod lockout;
\ No newline at end of file
+pub mod lockout;
+pub mod config;
+pub mod potd;
+pub mod streak;
+pub mod profile;
+pub mod contests;
+pub mod rating_changes;
+pub mod standings;
+pub mod track;
+pub mod virtual_contest;
+pub mod mashup;
+pub mod tournament;
+pub mod queue;
+pub mod veto;
+pub mod match_control;
\ No newline at end of file
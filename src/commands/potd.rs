@@ -0,0 +1,265 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use serenity::builder::{CreateEmbed, CreateMessage};
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::CommandResult;
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use rand::seq::SliceRandom;
+
+use tokio::time::{sleep, Duration};
+use tracing::{error, info};
+
+use crate::commands::duel::*;
+use crate::commands::giveme::ProblemFilter;
+use crate::commands::handle::*;
+use crate::commands::lockout::*;
+
+use crate::core::data::User;
+use crate::core::data::*;
+use crate::error_response;
+use crate::utils::message_creator::*;
+use crate::utils::time::*;
+
+const POTD_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+// how long to wait before trying to post again after a failed attempt
+const POTD_RETRY_SECONDS: u64 = 60 * 60;
+// recent submissions are enough to know who solved today's problem
+const TALLY_SUBMISSION_COUNT: i32 = 200;
+
+static POTD_SCHEDULER_STARTED: AtomicBool = AtomicBool::new(false);
+// the guilds a problem of the day was last tried to be posted for and when
+static POTD_ATTEMPTS: Mutex<Vec<(String, u64)>> = Mutex::new(Vec::new());
+
+// remember the attempt to post for the guild, false while the last one is too recent
+fn try_potd_attempt(guild_id: &str, now: u64) -> bool {
+  let mut attempts = match POTD_ATTEMPTS.lock() {
+    Ok(attempts) => attempts,
+    Err(_) => return false,
+  };
+  match attempts.iter_mut().find(|(guild, _)| guild == guild_id) {
+    Some((_, attempted_at)) if now < *attempted_at + POTD_RETRY_SECONDS => false,
+    Some((_, attempted_at)) => {
+      *attempted_at = now;
+      true
+    }
+    None => {
+      attempts.push((guild_id.to_owned(), now));
+      true
+    }
+  }
+}
+
+// members of the guild with an `OK` on the problem of the day since it was posted
+async fn get_potd_solvers(ctx: &Context, potd: &ProblemOfTheDay) -> Vec<(User, u64)> {
+  let users = match potd.guild_id.parse::<u64>() {
+    Ok(id) => get_guild_users(ctx, GuildId::new(id)).await,
+    Err(_) => return Vec::new(),
+  };
+  let mut solvers = Vec::new();
+  for user in users {
+    let submissions = match get_user_submission(&user.handle, TALLY_SUBMISSION_COUNT).await {
+      Ok(submissions) => submissions,
+      Err(_) => continue,
+    };
+    let solved_at = submissions
+      .iter()
+      .filter(|submission| {
+        submission.problem == potd.problem
+          && submission.verdict.as_deref() == Some("OK")
+          && submission.creationTimeSeconds >= potd.posted_at
+      })
+      .map(|submission| submission.creationTimeSeconds)
+      .min();
+    if let Some(time) = solved_at {
+      solvers.push((user, time));
+    }
+  }
+  solvers.sort_by_key(|(_, time)| *time);
+  solvers
+}
+
+fn get_solvers_string(solvers: &[(User, u64)]) -> String {
+  if solvers.is_empty() {
+    return "Nobody has solved it yet".to_owned();
+  }
+  solvers
+    .iter()
+    .enumerate()
+    .map(|(i, (user, time))| format!("{}. <@{}> at <t:{time}:t>", i + 1, user.userId))
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+fn create_tally_message(potd: &ProblemOfTheDay, solvers: &[(User, u64)]) -> CreateMessage {
+  let embed = CreateEmbed::new()
+    .title(format!(
+      "Problem of the day results: {index}. {name}",
+      index = potd.problem.index,
+      name = potd.problem.name
+    ))
+    .field(
+      format!("Solved by {} member(s)", solvers.len()),
+      get_solvers_string(solvers),
+      false,
+    )
+    .colour(Colour::GOLD)
+    .timestamp(Timestamp::now());
+  CreateMessage::new().embed(embed)
+}
+
+/*
+  Pick a problem in the rating band of the guild that none of its registered members solved.
+  Ratings of the band are tried in random order until one of them has a problem left
+*/
+async fn pick_potd_problem(
+  ctx: &Context,
+  guild_id: GuildId,
+  config: &PotdConfig,
+) -> Option<Problem> {
+  let pool = get_problem_pool(ctx, Some(guild_id), None).await;
+  let problem_set = get_pool_problemset(&pool).await.ok()?;
+  let users = get_guild_users(ctx, guild_id).await;
+  let mut user_submissions = get_all_user_submissions(&users).await;
+  if user_submissions.is_empty() {
+    user_submissions.push(Vec::new());
+  }
+
+  let mut ratings = (config.min_rating / 100..=config.max_rating / 100)
+    .map(|rating| rating * 100)
+    .collect::<Vec<_>>();
+  ratings.shuffle(&mut rand::thread_rng());
+  for rating in ratings {
+    if let Some(problem) = get_problem_for_users(
+      &users,
+      rating,
+      &problem_set,
+      &user_submissions,
+      &ProblemFilter::default(),
+    )
+    .await
+    {
+      return Some(problem);
+    }
+  }
+  None
+}
+
+async fn post_potd(ctx: &Context, guild_id: GuildId, config: &PotdConfig, day: i64) {
+  let problem = match pick_potd_problem(ctx, guild_id, config).await {
+    Some(problem) => problem,
+    None => {
+      error!("Can't find a problem of the day for guild {guild_id}");
+      return;
+    }
+  };
+  let message = match create_problem_message(
+    &problem,
+    "Problem of the day! Type `~potd` to see who has solved it".to_owned(),
+    true,
+  ) {
+    Some(message) => message,
+    None => return,
+  };
+  if let Err(why) = config.channel_id.send_message(&ctx.http, message).await {
+    error!("Can't post the problem of the day: {:?}", why);
+    return;
+  }
+  set_potd(
+    ctx,
+    ProblemOfTheDay {
+      guild_id: guild_id.to_string(),
+      channel_id: config.channel_id,
+      day,
+      problem,
+      posted_at: unix_now(),
+      tallied: false,
+    },
+  )
+  .await;
+}
+
+async fn check_potd(ctx: &Context, config: &GuildConfig) {
+  let potd_config = match &config.potd {
+    Some(potd_config) => potd_config,
+    None => return,
+  };
+  let guild_id = match config.guild_id.parse::<u64>() {
+    Ok(id) => GuildId::new(id),
+    Err(_) => return,
+  };
+  let now = unix_now();
+  let today = local_day(now, config.utc_offset);
+  let minute_of_day = local_minute_of_day(now, config.utc_offset);
+  let current = get_potd(ctx, &config.guild_id).await;
+
+  // the day of the last problem is over, announce who solved it
+  if let Some(mut potd) = current.clone() {
+    if potd.day < today && !potd.tallied {
+      let solvers = get_potd_solvers(ctx, &potd).await;
+      let _ = potd
+        .channel_id
+        .send_message(&ctx.http, create_tally_message(&potd, &solvers))
+        .await;
+      potd.tallied = true;
+      set_potd(ctx, potd).await;
+    }
+  }
+
+  let posted_today = current.is_some_and(|potd| potd.day >= today);
+  if !posted_today && minute_of_day >= potd_config.time && try_potd_attempt(&config.guild_id, now) {
+    info!("Posting the problem of the day for guild {guild_id}");
+    post_potd(ctx, guild_id, potd_config, today).await;
+  }
+}
+
+pub async fn potd_interactor(ctx: &Context) {
+  if POTD_SCHEDULER_STARTED.swap(true, Ordering::SeqCst) {
+    return;
+  }
+  let ctx = ctx.clone();
+  tokio::spawn(async move {
+    loop {
+      if let Ok(data) = get_data(&ctx).await {
+        for config in data.guilds.iter() {
+          check_potd(&ctx, config).await;
+        }
+      }
+      sleep(POTD_CHECK_INTERVAL).await;
+    }
+  });
+}
+
+#[command]
+#[only_in(guilds)]
+pub async fn potd(ctx: &Context, msg: &Message) -> CommandResult {
+  let guild_id = match msg.guild_id {
+    Some(id) => id.to_string(),
+    None => return Ok(()),
+  };
+  let potd = match get_potd(ctx, &guild_id).await {
+    Some(potd) => potd,
+    None => {
+      error_response!(
+        ctx,
+        msg,
+        "There is no problem of the day yet, an admin can set it up with `~config potd`".to_owned()
+      );
+      return Ok(());
+    }
+  };
+  let builder = create_await_message();
+  let message = msg.channel_id.send_message(&ctx.http, builder).await?;
+  let solvers = get_potd_solvers(ctx, &potd).await;
+  let embed = create_problem_embed(&potd.problem, true)
+    .description(format!("Posted <t:{}:R>", potd.posted_at))
+    .field(
+      format!("Solved by {} member(s)", solvers.len()),
+      get_solvers_string(&solvers),
+      false,
+    );
+  edit_to_message(ctx, embed, message).await;
+  Ok(())
+}
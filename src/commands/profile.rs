@@ -0,0 +1,110 @@
+use serenity::builder::CreateEmbed;
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::{Args, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use crate::commands::duel::*;
+use crate::commands::giveme::*;
+use crate::commands::rating::*;
+use crate::commands::streak::*;
+
+use crate::core::achievement::*;
+use crate::core::data::User;
+use crate::core::data::*;
+use crate::error_response;
+use crate::utils::message_creator::*;
+
+fn get_badges_string(user: &User) -> String {
+  Achievement::ALL
+    .iter()
+    .map(|achievement| {
+      match user
+        .achievements
+        .iter()
+        .find(|unlocked| unlocked.achievement == *achievement)
+      {
+        Some(unlocked) => format!(
+          "{emoji} **{name}** (<t:{time}:d>)",
+          emoji = achievement.emoji(),
+          name = achievement.name(),
+          time = unlocked.unlocked_at
+        ),
+        None => format!(
+          ":lock: ~~{name}~~ {description}",
+          name = achievement.name(),
+          description = achievement.description()
+        ),
+      }
+    })
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+fn create_profile_embed(user: &User, rating: Option<u32>, streak: Option<Streak>) -> CreateEmbed {
+  let rating = match rating {
+    Some(0) => "Unrated".to_owned(),
+    Some(rating) => rating.to_string(),
+    None => "Unknown".to_owned(),
+  };
+  let streak = match streak {
+    Some(streak) => format!("{} day(s)", streak.current),
+    None => "Unknown".to_owned(),
+  };
+  CreateEmbed::new()
+    .title(format!("Profile of {handle}", handle = user.handle))
+    .url(format!(
+      "https://codeforces.com/profile/{handle}",
+      handle = user.handle
+    ))
+    .field("Rating", rating, true)
+    .field("Challenge score", user.challenge_score.to_string(), true)
+    .field("Duels won", user.duels_won.to_string(), true)
+    .field("Streak", streak, true)
+    .field(
+      format!(
+        "Badges ({unlocked}/{total})",
+        unlocked = user.achievements.len(),
+        total = Achievement::ALL.len()
+      ),
+      get_badges_string(user),
+      false,
+    )
+    .colour(Colour::BLUE)
+    .timestamp(Timestamp::now())
+}
+
+#[command]
+pub async fn profile(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+  let mut user_id = msg.author.id;
+  if let Ok(mention) = args.single::<String>() {
+    match extract_user_id(mention.clone()) {
+      Some(id) => user_id = id,
+      None => {
+        error_response!(ctx, msg, format!("`{mention}` is not a mention of a user"));
+        return Ok(());
+      }
+    }
+  }
+  let user = match find_user_in_data(ctx, &user_id.to_string()).await {
+    Ok(user) => user,
+    Err(why) => {
+      error_response!(ctx, msg, why);
+      return Ok(());
+    }
+  };
+
+  let builder = create_await_message();
+  let message = msg.channel_id.send_message(&ctx.http, builder).await?;
+  let utc_offset = get_guild_config(ctx, msg.guild_id).await.utc_offset;
+  let rating = get_user_rating(&user.handle).await.ok();
+  let streak = get_user_streak(&user, utc_offset).await.ok();
+  if let Some(streak) = &streak {
+    let event = BotEvent::StreakUpdated(streak.current);
+    trigger_achievements(ctx, Some(msg.channel_id), &user.userId, event).await;
+  }
+  // the streak may have just unlocked a badge
+  let user = find_user_in_data(ctx, &user.userId).await.unwrap_or(user);
+  edit_to_message(ctx, create_profile_embed(&user, rating, streak), message).await;
+  Ok(())
+}
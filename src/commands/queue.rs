@@ -0,0 +1,293 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serenity::builder::{CreateEmbed, CreateMessage};
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::{Args, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use tokio::time::{sleep, Duration};
+use tracing::info;
+
+use crate::commands::duel::*;
+use crate::commands::giveme::*;
+use crate::commands::rating::*;
+
+use crate::core::data::*;
+use crate::error_response;
+use crate::utils::message_creator::*;
+use crate::utils::time::*;
+
+const QUEUE_INTERVAL: Duration = Duration::from_secs(15);
+// the rating gap accepted right after joining, it grows by `GAP_GROWTH` every minute
+const INITIAL_GAP: u32 = 100;
+const GAP_GROWTH: u32 = 50;
+const MAX_GAP: u32 = 800;
+
+static QUEUE_STARTED: AtomicBool = AtomicBool::new(false);
+
+async fn show_help() -> CreateMessage {
+  let embed = CreateEmbed::new()
+    .title("Usage of `queue`")
+    .description(format!(
+      "`~queue join [rating band (optional)]` (wait for a duel against a member with a similar rating, the accepted gap starts at {INITIAL_GAP} and grows by {GAP_GROWTH} every minute up to the band or {MAX_GAP})\n
+      `~queue leave`\n
+      `~queue` (who is waiting)"
+    ))
+    .color(Colour::DARK_GREEN);
+  CreateMessage::new().embed(embed)
+}
+
+// the rating gap the entry accepts after waiting until `now`
+pub fn get_accepted_gap(entry: &QueueEntry, now: u64) -> u32 {
+  let minutes = (now.saturating_sub(entry.joined_at) / 60) as u32;
+  let gap = INITIAL_GAP.saturating_add(GAP_GROWTH.saturating_mul(minutes));
+  gap.min(entry.band.unwrap_or(MAX_GAP))
+}
+
+fn can_pair(a: &QueueEntry, b: &QueueEntry, now: u64) -> bool {
+  a.message.guild_id == b.message.guild_id
+    && a.rating.abs_diff(b.rating) <= get_accepted_gap(a, now).min(get_accepted_gap(b, now))
+}
+
+/*
+  Pair whoever waited the longest with the closest rating they can accept, then the next one and
+  so on. Returns the indices of the paired entries
+*/
+pub fn find_pairs(queue: &[QueueEntry], now: u64) -> Vec<(usize, usize)> {
+  let mut order = (0..queue.len()).collect::<Vec<_>>();
+  order.sort_by_key(|index| queue[*index].joined_at);
+  let mut paired = vec![false; queue.len()];
+  let mut pairs = Vec::new();
+  for &i in order.iter() {
+    if paired[i] {
+      continue;
+    }
+    let partner = order
+      .iter()
+      .filter(|j| **j != i && !paired[**j] && can_pair(&queue[i], &queue[**j], now))
+      .min_by_key(|j| queue[i].rating.abs_diff(queue[**j].rating));
+    if let Some(&j) = partner {
+      paired[i] = true;
+      paired[j] = true;
+      pairs.push((i, j));
+    }
+  }
+  pairs
+}
+
+async fn start_queued_duel(ctx: &Context, first: &QueueEntry, second: &QueueEntry) {
+  let mut users = Vec::new();
+  for entry in [first, second] {
+    if let Ok(user) = find_user_in_data(ctx, &entry.user_id).await {
+      if user.duel_id.is_none() {
+        users.push(user);
+      }
+    }
+  }
+  // one of them started something else in the meantime, the other one keeps waiting
+  if users.len() < 2 {
+    let free = [first, second]
+      .into_iter()
+      .filter(|entry| users.iter().any(|user| user.userId == entry.user_id))
+      .cloned()
+      .collect::<Vec<_>>();
+    edit_data(ctx, |data| data.queue.extend(free)).await;
+    return;
+  }
+  info!("Queue paired {} and {}", first.user_id, second.user_id);
+  let msg = &first.message;
+  let announcement = CreateEmbed::new()
+    .description(format!(
+      "Found a duel: <@{a}> ({a_rating}) vs <@{b}> ({b_rating})",
+      a = first.user_id,
+      a_rating = first.rating,
+      b = second.user_id,
+      b_rating = second.rating
+    ))
+    .colour(Colour::BLUE);
+  let _ = msg
+    .channel_id
+    .send_message(&ctx.http, CreateMessage::new().embed(announcement.clone()))
+    .await;
+  if second.message.channel_id != msg.channel_id {
+    let notice = announcement.field("Where", format!("<#{}>", msg.channel_id), false);
+    let _ = second
+      .message
+      .channel_id
+      .send_message(&ctx.http, CreateMessage::new().embed(notice))
+      .await;
+  }
+  let pool = get_problem_pool(ctx, msg.guild_id, None).await;
+  let rating = get_average_rating(&users).await;
+  handle_duel(ctx, msg, users, rating, &pool, &ProblemFilter::default()).await;
+}
+
+async fn match_queue(ctx: &Context) {
+  let waiting = match get_data(ctx).await {
+    Ok(data) if data.queue.len() >= 2 => data.queue,
+    _ => return,
+  };
+  // whoever is in a match keeps their place until they are free again
+  let mut queue = Vec::new();
+  for entry in waiting {
+    if matches!(find_user_in_data(ctx, &entry.user_id).await, Ok(user) if user.duel_id.is_none()) {
+      queue.push(entry);
+    }
+  }
+  let pairs = find_pairs(&queue, unix_now());
+  if pairs.is_empty() {
+    return;
+  }
+  let paired = pairs
+    .iter()
+    .flat_map(|(i, j)| [queue[*i].user_id.clone(), queue[*j].user_id.clone()])
+    .collect::<Vec<_>>();
+  edit_data(ctx, |data| {
+    data.queue.retain(|entry| !paired.contains(&entry.user_id))
+  })
+  .await;
+  // the veto of a duel takes minutes, the other pairs and the next tick don't wait for it
+  for (i, j) in pairs {
+    let ctx = ctx.clone();
+    let (first, second) = (queue[i].clone(), queue[j].clone());
+    tokio::spawn(async move {
+      start_queued_duel(&ctx, &first, &second).await;
+    });
+  }
+}
+
+pub async fn queue_interactor(ctx: &Context) {
+  if QUEUE_STARTED.swap(true, Ordering::SeqCst) {
+    return;
+  }
+  let ctx = ctx.clone();
+  tokio::spawn(async move {
+    loop {
+      match_queue(&ctx).await;
+      sleep(QUEUE_INTERVAL).await;
+    }
+  });
+}
+
+fn create_queue_embed(queue: &[QueueEntry], guild_id: Option<GuildId>) -> CreateEmbed {
+  let now = unix_now();
+  let waiting = queue
+    .iter()
+    .filter(|entry| entry.message.guild_id == guild_id)
+    .map(|entry| {
+      format!(
+        "<@{user_id}> ({rating}) waiting for {waited}, accepts ±{gap}",
+        user_id = entry.user_id,
+        rating = entry.rating,
+        waited = format_duration(now.saturating_sub(entry.joined_at)),
+        gap = get_accepted_gap(entry, now)
+      )
+    })
+    .collect::<Vec<_>>();
+  CreateEmbed::new()
+    .title("Duel queue")
+    .description(if waiting.is_empty() {
+      "Nobody is waiting, type `~queue join` to start".to_owned()
+    } else {
+      waiting.join("\n")
+    })
+    .colour(Colour::BLUE)
+}
+
+#[command]
+pub async fn queue(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+  let option = args.single::<String>().unwrap_or_default();
+  let user_id = msg.author.id.to_string();
+  let queue = match get_data(ctx).await {
+    Ok(data) => data.queue,
+    Err(why) => {
+      error_response!(ctx, msg, why);
+      return Ok(());
+    }
+  };
+  let waiting = queue.iter().any(|entry| entry.user_id == user_id);
+  match option.as_str() {
+    "join" => {
+      let band = match args.single::<String>() {
+        Ok(band) => match band.parse::<u32>() {
+          Ok(band) => Some(band),
+          Err(_) => {
+            error_response!(ctx, msg, format!("`{band}` is not a rating band"));
+            return Ok(());
+          }
+        },
+        Err(_) => None,
+      };
+      let user = match find_user_in_data(ctx, &user_id).await {
+        Ok(user) => user,
+        Err(why) => {
+          error_response!(ctx, msg, why);
+          return Ok(());
+        }
+      };
+      if waiting {
+        error_response!(ctx, msg, "You are already in the queue".to_owned());
+        return Ok(());
+      }
+      if user.duel_id.is_some() {
+        error_response!(ctx, msg, "You are already in a match".to_owned());
+        return Ok(());
+      }
+      let rating = match get_user_rating(&user.handle).await {
+        Ok(0) => DEFAULT_RATING as u32,
+        Ok(rating) => rating,
+        Err(why) => {
+          error_response!(ctx, msg, why);
+          return Ok(());
+        }
+      };
+      let entry = QueueEntry {
+        user_id,
+        rating,
+        band,
+        joined_at: unix_now(),
+        message: msg.clone(),
+      };
+      edit_data(ctx, |data| data.queue.push(entry)).await;
+      let embed = CreateEmbed::new()
+        .description(format!(
+          "<@{}> joined the queue with a rating of {rating}",
+          msg.author.id
+        ))
+        .colour(Colour::DARK_GREEN);
+      msg
+        .channel_id
+        .send_message(&ctx.http, CreateMessage::new().embed(embed))
+        .await?;
+    }
+    "leave" => {
+      if !waiting {
+        error_response!(ctx, msg, "You aren't in the queue".to_owned());
+        return Ok(());
+      }
+      edit_data(ctx, |data| {
+        data.queue.retain(|entry| entry.user_id != user_id)
+      })
+      .await;
+      let embed = CreateEmbed::new()
+        .description(format!("<@{}> left the queue", msg.author.id))
+        .colour(Colour::DARK_GREEN);
+      msg
+        .channel_id
+        .send_message(&ctx.http, CreateMessage::new().embed(embed))
+        .await?;
+    }
+    "" => {
+      let message = CreateMessage::new().embed(create_queue_embed(&queue, msg.guild_id));
+      msg.channel_id.send_message(&ctx.http, message).await?;
+    }
+    _ => {
+      msg
+        .channel_id
+        .send_message(&ctx.http, show_help().await)
+        .await?;
+    }
+  }
+  Ok(())
+}
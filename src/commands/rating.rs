@@ -11,27 +11,66 @@ use crate::utils::message_creator::*;
 use crate::error_response;
 
 #[allow(non_snake_case)]
-#[derive(Serialize, Deserialize, Debug)]
-struct Contest {
-  contestId : u32,
-  contestName: String,
-  handle: String,
-  rank: u32,
-  ratingUpdateTimeSeconds: u64,
-  oldRating: u32,
-  newRating: u32,
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct RatingChange {
+  pub contestId : u32,
+  pub contestName: String,
+  pub handle: String,
+  pub rank: u32,
+  pub ratingUpdateTimeSeconds: u64,
+  pub oldRating: u32,
+  pub newRating: u32,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 struct APIRespone {
   status: String,
-  result: Vec<Contest>,
+  result: Vec<RatingChange>,
 }
 
-fn get_rating (contest : &Contest) -> u32 {
+fn get_rating (contest : &RatingChange) -> u32 {
   contest.newRating
 }
 
+// the codeforces title that goes with a rating, a new title means a new colour
+pub fn rank_title(rating: u32) -> &'static str {
+  match rating {
+    0..=1199 => "Newbie",
+    1200..=1399 => "Pupil",
+    1400..=1599 => "Specialist",
+    1600..=1899 => "Expert",
+    1900..=2099 => "Candidate Master",
+    2100..=2299 => "Master",
+    2300..=2399 => "International Master",
+    2400..=2599 => "Grandmaster",
+    2600..=2999 => "International Grandmaster",
+    _ => "Legendary Grandmaster",
+  }
+}
+
+/*
+  Rating changes of every rated participant, empty while the contest isn't rated yet and None when
+  it is unrated, codeforces refuses to give them then
+*/
+pub async fn get_contest_rating_changes(contest_id: u32) -> Result<Option<Vec<RatingChange>>, String> {
+  let client = Client::new();
+  let url = format!("https://codeforces.com/api/contest.ratingChanges?contestId={contest_id}");
+  let result = match client.get(url).send().await {
+    Ok(result) => result,
+    Err(_) => return Err("Codeforces API error".to_owned()),
+  };
+  match result.status() {
+    reqwest::StatusCode::OK => {
+      match result.json::<APIRespone>().await {
+        Ok(parsed) => Ok(Some(parsed.result)),
+        Err(_) => Err("Failed to match json".to_owned()),
+      }
+    },
+    reqwest::StatusCode::BAD_REQUEST => Ok(None),
+    _ => Err(format!("Rating changes of contest `{contest_id}` are unavailable")),
+  }
+}
+
 pub async fn get_user_rating(user: &String) -> Result<u32, String> {
   let client = Client::new();
   let url = format!("https://codeforces.com/api/user.rating?handle={handle}", handle = user);
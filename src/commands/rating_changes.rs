@@ -0,0 +1,242 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serenity::builder::{CreateEmbed, CreateMessage};
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::{Args, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use tokio::time::{sleep, Duration};
+use tracing::{error, info};
+
+use crate::commands::rating::*;
+
+use crate::core::data::User;
+use crate::core::data::*;
+use crate::error_response;
+use crate::utils::message_creator::*;
+use crate::utils::time::*;
+
+const RATING_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 15);
+// rounds that ended longer ago than this are never announced, ratings come out within a day or two
+const RATING_WINDOW: u64 = 60 * 60 * 24 * 3;
+
+// keeps the summary under the length limit of an embed description
+const CHANGES_SHOWN: usize = 30;
+
+static RATING_WATCHER_STARTED: AtomicBool = AtomicBool::new(false);
+
+// rating changes of the registered members, best rank first
+fn get_member_changes(changes: Vec<RatingChange>, users: &[User]) -> Vec<RatingChange> {
+  let mut member_changes = changes
+    .into_iter()
+    .filter(|change| {
+      users
+        .iter()
+        .any(|user| user.handle.eq_ignore_ascii_case(&change.handle))
+    })
+    .collect::<Vec<_>>();
+  member_changes.sort_by_key(|change| change.rank);
+  member_changes
+}
+
+fn get_changes_string(changes: &[RatingChange]) -> String {
+  let mut lines = changes
+    .iter()
+    .take(CHANGES_SHOWN)
+    .map(|change| {
+      let delta = change.newRating as i64 - change.oldRating as i64;
+      format!(
+        "`#{rank}` {user_link} {old} → {new} (**{delta:+}**)",
+        rank = change.rank,
+        user_link = create_href(
+          format!(
+            "https://codeforces.com/profile/{handle}",
+            handle = change.handle
+          ),
+          &change.handle
+        ),
+        old = change.oldRating,
+        new = change.newRating
+      )
+    })
+    .collect::<Vec<_>>();
+  if changes.len() > CHANGES_SHOWN {
+    lines.push(format!("and {} more", changes.len() - CHANGES_SHOWN));
+  }
+  lines.join("\n")
+}
+
+fn get_new_colours_string(changes: &[RatingChange]) -> Option<String> {
+  let promotions = changes
+    .iter()
+    .filter(|change| rank_title(change.oldRating) != rank_title(change.newRating))
+    .map(|change| {
+      let direction = if change.newRating > change.oldRating {
+        ":arrow_up:"
+      } else {
+        ":arrow_down:"
+      };
+      format!(
+        "{direction} {handle} is now {title}",
+        handle = change.handle,
+        title = rank_title(change.newRating)
+      )
+    })
+    .collect::<Vec<_>>();
+  if promotions.is_empty() {
+    None
+  } else {
+    Some(promotions.join("\n"))
+  }
+}
+
+fn create_rating_changes_embed(changes: &[RatingChange]) -> CreateEmbed {
+  let mut embed = CreateEmbed::new()
+    .title(format!("Rating changes: {}", changes[0].contestName))
+    .url(format!(
+      "https://codeforces.com/contest/{id}/standings",
+      id = changes[0].contestId
+    ))
+    .description(get_changes_string(changes))
+    .colour(Colour::BLUE)
+    .timestamp(Timestamp::now());
+  if let Some(new_colours) = get_new_colours_string(changes) {
+    embed = embed.field("New colours", new_colours, false);
+  }
+  embed
+}
+
+/*
+  Post the rating changes of the members of every guild to its rating channel. Returns whether the
+  contest is done with, because it was announced or because it isn't rated
+*/
+async fn announce_contest(ctx: &Context, contest: &Contest, data: &Data) -> bool {
+  let changes = match get_contest_rating_changes(contest.id).await {
+    Ok(Some(changes)) if !changes.is_empty() => changes,
+    Ok(None) => return true,
+    // not rated yet, try again later
+    _ => return false,
+  };
+  info!("Announcing the rating changes of contest {}", contest.id);
+  for config in data.guilds.iter() {
+    let channel_id = match config.rating_channel {
+      Some(channel_id) => channel_id,
+      None => continue,
+    };
+    let guild_id = match config.guild_id.parse::<u64>() {
+      Ok(id) => GuildId::new(id),
+      Err(_) => continue,
+    };
+    let members = get_guild_users(ctx, guild_id).await;
+    let changes = get_member_changes(changes.clone(), &members);
+    if changes.is_empty() {
+      continue;
+    }
+    let message = CreateMessage::new().embed(create_rating_changes_embed(&changes));
+    if let Err(why) = channel_id.send_message(&ctx.http, message).await {
+      error!("Can't post the rating changes: {:?}", why);
+    }
+  }
+  true
+}
+
+async fn check_rated_contests(ctx: &Context) {
+  let data = match get_data(ctx).await {
+    Ok(data) => data,
+    Err(_) => return,
+  };
+  if data
+    .guilds
+    .iter()
+    .all(|config| config.rating_channel.is_none())
+  {
+    return;
+  }
+  let contests = match get_contests(false).await {
+    Ok(contests) => contests,
+    Err(_) => return,
+  };
+  let now = unix_now();
+  let recent = contests
+    .iter()
+    .filter(|contest| {
+      let end = contest.startTimeSeconds.unwrap_or_default() + contest.durationSeconds;
+      end + RATING_WINDOW >= now
+    })
+    .collect::<Vec<_>>();
+  // the contests that left the window are never checked again
+  let recent_ids = recent.iter().map(|contest| contest.id).collect::<Vec<_>>();
+  if data
+    .rated_contests
+    .iter()
+    .any(|id| !recent_ids.contains(id))
+  {
+    edit_data(ctx, |data| {
+      data.rated_contests.retain(|id| recent_ids.contains(id))
+    })
+    .await;
+  }
+  let finished = recent
+    .into_iter()
+    .filter(|contest| contest.phase == "FINISHED" && !data.rated_contests.contains(&contest.id));
+  for contest in finished {
+    if announce_contest(ctx, contest, &data).await {
+      edit_data(ctx, |data| data.rated_contests.push(contest.id)).await;
+    }
+  }
+}
+
+pub async fn rating_change_interactor(ctx: &Context) {
+  if RATING_WATCHER_STARTED.swap(true, Ordering::SeqCst) {
+    return;
+  }
+  let ctx = ctx.clone();
+  tokio::spawn(async move {
+    loop {
+      check_rated_contests(&ctx).await;
+      sleep(RATING_CHECK_INTERVAL).await;
+    }
+  });
+}
+
+#[command]
+#[aliases("ratingchanges")]
+pub async fn rating_changes(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+  let contest_id = match args.single::<u32>() {
+    Ok(contest_id) => contest_id,
+    Err(_) => {
+      error_response!(ctx, msg, "Usage: `~ratingchanges <contest id>`".to_owned());
+      return Ok(());
+    }
+  };
+  let users = match get_data(ctx).await {
+    Ok(data) => keep_guild_members(ctx, msg.guild_id, data.data).await,
+    Err(why) => {
+      error_response!(ctx, msg, why);
+      return Ok(());
+    }
+  };
+  let changes = match get_contest_rating_changes(contest_id).await {
+    Ok(Some(changes)) => get_member_changes(changes, &users),
+    Ok(None) => {
+      error_response!(ctx, msg, format!("Contest `{contest_id}` is unrated"));
+      return Ok(());
+    }
+    Err(why) => {
+      error_response!(ctx, msg, why);
+      return Ok(());
+    }
+  };
+  if changes.is_empty() {
+    error_response!(
+      ctx,
+      msg,
+      format!("No registered member is rated in contest `{contest_id}`")
+    );
+    return Ok(());
+  }
+  let message = CreateMessage::new().embed(create_rating_changes_embed(&changes));
+  msg.channel_id.send_message(&ctx.http, message).await?;
+  Ok(())
+}
@@ -0,0 +1,215 @@
+use serenity::builder::{CreateEmbed, CreateEmbedFooter, CreateMessage};
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::{Args, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use crate::commands::giveme::*;
+use crate::commands::handle::*;
+
+use crate::core::data::User;
+use crate::core::data::*;
+use crate::error_response;
+use crate::utils::message_creator::*;
+
+const PAGE_SIZE: usize = 15;
+// practice and virtual participations don't belong in the standings of the round
+const ROUND_PARTICIPANTS: [&str; 2] = ["CONTESTANT", "OUT_OF_COMPETITION"];
+
+async fn show_help() -> CreateMessage {
+  let embed = CreateEmbed::new()
+    .title("Usage of `standings`")
+    .description(
+      "`~standings <contest id> [page]` (standings of the registered members in a codeforces round)",
+    )
+    .color(Colour::DARK_GREEN);
+  CreateMessage::new().embed(embed)
+}
+
+// rows of the registered members in the round, with their rank among the members
+pub async fn get_member_standing(
+  users: &[User],
+  contest_id: u32,
+) -> Result<(ContestResults, Vec<(usize, RanklistRow)>), String> {
+  if users.is_empty() {
+    return Err("There is no registered member yet".to_owned());
+  }
+  let handles = users
+    .iter()
+    .map(|user| user.handle.clone())
+    .collect::<Vec<_>>();
+  let results = get_contest_standing_of_handles(contest_id, &handles).await?;
+  let ranked = rank_locally(&results.rows);
+  Ok((results, ranked))
+}
+
+/*
+  Sort by points then penalty, like codeforces does. Tied members share a rank, so the ranks
+  can skip numbers (1, 1, 3)
+*/
+pub fn rank_locally(rows: &[RanklistRow]) -> Vec<(usize, RanklistRow)> {
+  let mut rows = rows
+    .iter()
+    .filter(|row| ROUND_PARTICIPANTS.contains(&row.party.participantType.as_str()))
+    .cloned()
+    .collect::<Vec<_>>();
+  rows.sort_by(|a, b| {
+    b.points
+      .total_cmp(&a.points)
+      .then(a.penalty.cmp(&b.penalty))
+  });
+  let mut ranked: Vec<(usize, RanklistRow)> = Vec::new();
+  for (pos, row) in rows.into_iter().enumerate() {
+    let rank = match ranked.last() {
+      Some((rank, previous))
+        if previous.points == row.points && previous.penalty == row.penalty =>
+      {
+        *rank
+      }
+      _ => pos + 1,
+    };
+    ranked.push((rank, row));
+  }
+  ranked
+}
+
+// indices of the problems the row got points for
+pub fn get_solved_indices(problems: &[Problem], row: &RanklistRow) -> Vec<String> {
+  problems
+    .iter()
+    .zip(row.problemResults.iter())
+    .filter(|(_, result)| result.points > 0f64)
+    .map(|(problem, _)| problem.index.clone())
+    .collect()
+}
+
+pub fn get_party_string(party: &Author, users: &[User]) -> String {
+  party
+    .members
+    .iter()
+    .map(|member| {
+      match users
+        .iter()
+        .find(|user| user.handle.eq_ignore_ascii_case(&member.handle))
+      {
+        Some(user) => format!(
+          "{handle} (<@{id}>)",
+          handle = member.handle,
+          id = user.userId
+        ),
+        None => member.handle.clone(),
+      }
+    })
+    .collect::<Vec<_>>()
+    .join(", ")
+}
+
+pub fn get_row_string(
+  rank: usize,
+  row: &RanklistRow,
+  results: &ContestResults,
+  users: &[User],
+) -> String {
+  let solved = get_solved_indices(&results.problems, row);
+  let score = if results.contest.r#type == "ICPC" {
+    format!(
+      "**{solved}** solved, penalty {penalty}",
+      solved = solved.len(),
+      penalty = row.penalty
+    )
+  } else {
+    format!("**{points}** points", points = row.points)
+  };
+  format!(
+    "`{rank}.` {party} {score} {solved}",
+    party = get_party_string(&row.party, users),
+    solved = if solved.is_empty() {
+      String::new()
+    } else {
+      format!("[{}]", solved.join(" "))
+    }
+  )
+}
+
+fn create_standings_embed(
+  results: &ContestResults,
+  ranked: &[(usize, RanklistRow)],
+  users: &[User],
+  page: usize,
+) -> CreateEmbed {
+  let pages = ranked.len().div_ceil(PAGE_SIZE).max(1);
+  let rows = ranked
+    .iter()
+    .skip((page - 1) * PAGE_SIZE)
+    .take(PAGE_SIZE)
+    .map(|(rank, row)| get_row_string(*rank, row, results, users))
+    .collect::<Vec<_>>();
+  CreateEmbed::new()
+    .title(format!("Standings: {}", results.contest.name))
+    .url(format!(
+      "https://codeforces.com/contest/{id}/standings",
+      id = results.contest.id
+    ))
+    .description(if rows.is_empty() {
+      "No registered member took part in this round".to_owned()
+    } else {
+      rows.join("\n")
+    })
+    .colour(Colour::BLUE)
+    .footer(CreateEmbedFooter::new(format!(
+      "Page {page}/{pages}, {count} member(s)",
+      count = ranked.len()
+    )))
+    .timestamp(Timestamp::now())
+}
+
+#[command]
+pub async fn standings(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+  let contest_id = match args.single::<u32>() {
+    Ok(contest_id) => contest_id,
+    Err(_) => {
+      msg
+        .channel_id
+        .send_message(&ctx.http, show_help().await)
+        .await?;
+      return Ok(());
+    }
+  };
+  let page = args.single::<usize>().unwrap_or(1).max(1);
+
+  let builder = create_await_message();
+  let message = msg.channel_id.send_message(&ctx.http, builder).await?;
+  let users = match get_data(ctx).await {
+    Ok(data) => keep_guild_members(ctx, msg.guild_id, data.data).await,
+    Err(why) => {
+      edit_to_failed_status(ctx, message).await;
+      error_response!(ctx, msg, why);
+      return Ok(());
+    }
+  };
+  let (results, ranked) = match get_member_standing(&users, contest_id).await {
+    Ok(standing) => standing,
+    Err(why) => {
+      edit_to_failed_status(ctx, message).await;
+      error_response!(ctx, msg, why);
+      return Ok(());
+    }
+  };
+  let pages = ranked.len().div_ceil(PAGE_SIZE).max(1);
+  if page > pages {
+    edit_to_failed_status(ctx, message).await;
+    error_response!(
+      ctx,
+      msg,
+      format!("There are only {pages} page(s) of standings")
+    );
+    return Ok(());
+  }
+  edit_to_message(
+    ctx,
+    create_standings_embed(&results, &ranked, &users, page),
+    message,
+  )
+  .await;
+  Ok(())
+}
@@ -0,0 +1,322 @@
+use std::collections::BTreeSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serenity::builder::{CreateEmbed, CreateMessage};
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::{Args, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use tokio::time::{sleep, Duration};
+use tracing::error;
+
+use crate::commands::duel::*;
+use crate::commands::giveme::*;
+use crate::commands::handle::*;
+use crate::commands::lockout::*;
+
+use crate::core::achievement::*;
+use crate::core::data::User;
+use crate::core::data::*;
+use crate::error_response;
+use crate::utils::message_creator::*;
+use crate::utils::time::*;
+
+const STREAK_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 10);
+// reminders go out from this local time when nothing was solved during the day
+const REMINDER_TIME: u32 = 20 * 60;
+const LEADERBOARD_SIZE: usize = 10;
+
+static STREAK_SCHEDULER_STARTED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Streak {
+  pub current: u32,
+  pub longest: u32,
+  pub solved_today: bool,
+}
+
+async fn show_help() -> CreateMessage {
+  let embed = CreateEmbed::new()
+    .title("Usage of `streak`")
+    .description(
+      "`~streak [@user]` (days in a row with at least one accepted submission)\n
+      `~streak top` (streak leaderboard of the registered users)\n
+      `~streak remind <on / off>` (get a DM in the evening when your streak is about to break)",
+    )
+    .color(Colour::DARK_GREEN);
+  CreateMessage::new().embed(embed)
+}
+
+/*
+  A day counts when it has an accepted submission in the timezone `utc_offset`. A streak that
+  hasn't been extended today yet is still alive as long as yesterday counted
+*/
+pub fn compute_streak(submissions: &[Submission], utc_offset: i32, now: u64) -> Streak {
+  let days = submissions
+    .iter()
+    .filter(|submission| submission.verdict.as_deref() == Some("OK"))
+    .map(|submission| local_day(submission.creationTimeSeconds, utc_offset))
+    .collect::<BTreeSet<_>>();
+
+  let mut longest = 0;
+  let mut run = 0;
+  let mut previous: Option<i64> = None;
+  for day in days.iter() {
+    run = match previous {
+      Some(previous) if previous + 1 == *day => run + 1,
+      _ => 1,
+    };
+    longest = longest.max(run);
+    previous = Some(*day);
+  }
+
+  let today = local_day(now, utc_offset);
+  let solved_today = days.contains(&today);
+  let mut day = if solved_today { today } else { today - 1 };
+  let mut current = 0;
+  while days.contains(&day) {
+    current += 1;
+    day -= 1;
+  }
+  Streak {
+    current,
+    longest,
+    solved_today,
+  }
+}
+
+pub async fn get_user_streak(user: &User, utc_offset: i32) -> Result<Streak, String> {
+  let submission_count = 99999; // We want to get all user submissions
+  let submissions = get_user_submission(&user.handle, submission_count).await?;
+  Ok(compute_streak(&submissions, utc_offset, unix_now()))
+}
+
+fn create_streak_message(user: &User, streak: &Streak) -> CreateMessage {
+  let embed = CreateEmbed::new()
+    .title(format!("Streak of {handle}", handle = user.handle))
+    .field("Current streak", format!("{} day(s)", streak.current), true)
+    .field("Longest streak", format!("{} day(s)", streak.longest), true)
+    .field(
+      "Today",
+      if streak.solved_today {
+        "Solved :white_check_mark:"
+      } else {
+        "Not solved yet"
+      },
+      true,
+    )
+    .colour(if streak.current > 0 {
+      Colour::ORANGE
+    } else {
+      Colour::LIGHT_GREY
+    })
+    .timestamp(Timestamp::now());
+  CreateMessage::new()
+    .content(format!("<@{id}>", id = user.userId))
+    .embed(embed)
+}
+
+async fn show_leaderboard(ctx: &Context, msg: &Message, utc_offset: i32) -> CommandResult {
+  let builder = create_await_message();
+  let message = msg.channel_id.send_message(&ctx.http, builder).await?;
+  let users = match get_data(ctx).await {
+    Ok(data) => keep_guild_members(ctx, msg.guild_id, data.data).await,
+    Err(why) => {
+      error_response!(ctx, msg, why);
+      return Ok(());
+    }
+  };
+  let user_submissions = get_all_user_submissions(&users).await;
+  let now = unix_now();
+  let mut streaks = users
+    .iter()
+    .zip(user_submissions.iter())
+    .map(|(user, submissions)| (user, compute_streak(submissions, utc_offset, now)))
+    .filter(|(_, streak)| streak.current > 0)
+    .collect::<Vec<_>>();
+  streaks.sort_by(|(_, a), (_, b)| b.current.cmp(&a.current).then(b.longest.cmp(&a.longest)));
+
+  let leaderboard = if streaks.is_empty() {
+    "Nobody has an active streak".to_owned()
+  } else {
+    streaks
+      .iter()
+      .take(LEADERBOARD_SIZE)
+      .enumerate()
+      .map(|(i, (user, streak))| {
+        format!(
+          "{position}. <@{id}> **{current}** day(s) (longest {longest})",
+          position = i + 1,
+          id = user.userId,
+          current = streak.current,
+          longest = streak.longest
+        )
+      })
+      .collect::<Vec<_>>()
+      .join("\n")
+  };
+  let embed = CreateEmbed::new()
+    .title("Streak leaderboard")
+    .description(leaderboard)
+    .colour(Colour::ORANGE)
+    .timestamp(Timestamp::now());
+  edit_to_message(ctx, embed, message).await;
+  Ok(())
+}
+
+async fn remind_user(ctx: &Context, user: &User) {
+  let utc_offset = match &user.streak_reminder {
+    Some(guild_id) => {
+      let guild_id = guild_id.parse::<u64>().ok().map(GuildId::new);
+      get_guild_config(ctx, guild_id).await.utc_offset
+    }
+    None => return,
+  };
+  let now = unix_now();
+  let today = local_day(now, utc_offset);
+  if user.last_streak_reminder == Some(today)
+    || local_minute_of_day(now, utc_offset) < REMINDER_TIME
+  {
+    return;
+  }
+  let streak = match get_user_streak(user, utc_offset).await {
+    Ok(streak) => streak,
+    Err(_) => return,
+  };
+  let event = BotEvent::StreakUpdated(streak.current);
+  trigger_achievements(ctx, None, &user.userId, event).await;
+  edit_user(ctx, &user.userId, |user| {
+    user.last_streak_reminder = Some(today)
+  })
+  .await;
+  if streak.current == 0 || streak.solved_today {
+    return;
+  }
+
+  let user_id = match user.userId.parse::<u64>() {
+    Ok(id) => UserId::new(id),
+    Err(_) => return,
+  };
+  let embed = CreateEmbed::new()
+    .description(format!(
+      "Your {days} day streak ends at midnight, solve a problem to keep it going!",
+      days = streak.current
+    ))
+    .colour(Colour::ORANGE);
+  if let Err(why) = user_id
+    .direct_message(&ctx.http, CreateMessage::new().embed(embed))
+    .await
+  {
+    error!("Can't send the streak reminder: {:?}", why);
+  }
+}
+
+pub async fn streak_interactor(ctx: &Context) {
+  if STREAK_SCHEDULER_STARTED.swap(true, Ordering::SeqCst) {
+    return;
+  }
+  let ctx = ctx.clone();
+  tokio::spawn(async move {
+    loop {
+      if let Ok(data) = get_data(&ctx).await {
+        for user in data.data.iter() {
+          remind_user(&ctx, user).await;
+        }
+      }
+      sleep(STREAK_CHECK_INTERVAL).await;
+    }
+  });
+}
+
+#[command]
+pub async fn streak(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+  let utc_offset = get_guild_config(ctx, msg.guild_id).await.utc_offset;
+  let mut user_id = msg.author.id;
+  if let Ok(option) = args.single::<String>() {
+    match option.as_str() {
+      "help" | "h" => {
+        msg
+          .channel_id
+          .send_message(&ctx.http, show_help().await)
+          .await?;
+        return Ok(());
+      }
+      "top" | "leaderboard" => return show_leaderboard(ctx, msg, utc_offset).await,
+      "remind" => {
+        let user = match find_user_in_data(ctx, &msg.author.id.to_string()).await {
+          Ok(user) => user,
+          Err(why) => {
+            error_response!(ctx, msg, why);
+            return Ok(());
+          }
+        };
+        let reminder = match args.single::<String>().as_deref() {
+          Ok("on") => match msg.guild_id {
+            Some(guild_id) => Some(guild_id.to_string()),
+            None => {
+              error_response!(
+                ctx,
+                msg,
+                "Turn reminders on from a server so its timezone can be used".to_owned()
+              );
+              return Ok(());
+            }
+          },
+          Ok("off") => None,
+          _ => {
+            error_response!(ctx, msg, "Please use `on` or `off`".to_owned());
+            return Ok(());
+          }
+        };
+        let description = if reminder.is_some() {
+          "You will get a DM in the evening when your streak is about to break"
+        } else {
+          "Streak reminders are off"
+        };
+        edit_user(ctx, &user.userId, |user| user.streak_reminder = reminder).await;
+        let embed = CreateEmbed::new()
+          .description(description)
+          .colour(Colour::DARK_GREEN);
+        msg
+          .channel_id
+          .send_message(&ctx.http, CreateMessage::new().embed(embed))
+          .await?;
+        return Ok(());
+      }
+      mention => match extract_user_id(mention.to_owned()) {
+        Some(id) => user_id = id,
+        None => {
+          error_response!(
+            ctx,
+            msg,
+            format!("Unknown argument `{mention}`, try `~streak help`")
+          );
+          return Ok(());
+        }
+      },
+    }
+  }
+
+  let user = match find_user_in_data(ctx, &user_id.to_string()).await {
+    Ok(user) => user,
+    Err(why) => {
+      error_response!(ctx, msg, why);
+      return Ok(());
+    }
+  };
+  match get_user_streak(&user, utc_offset).await {
+    Ok(streak) => {
+      msg
+        .channel_id
+        .send_message(&ctx.http, create_streak_message(&user, &streak))
+        .await?;
+      let event = BotEvent::StreakUpdated(streak.current);
+      trigger_achievements(ctx, Some(msg.channel_id), &user.userId, event).await;
+    }
+    Err(why) => {
+      error_response!(ctx, msg, why);
+    }
+  }
+  Ok(())
+}
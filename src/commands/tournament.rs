@@ -0,0 +1,494 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::SystemTime;
+
+use serenity::builder::{CreateEmbed, CreateMessage};
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::{Args, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use tokio::sync::Mutex;
+use tokio::time::{sleep, Duration};
+use tracing::{error, info};
+
+use crate::commands::duel::*;
+use crate::commands::giveme::*;
+use crate::commands::lockout::*;
+use crate::commands::rating::*;
+
+use crate::core::data::*;
+use crate::core::tournament::*;
+use crate::error_response;
+use crate::utils::message_creator::*;
+
+const TOURNAMENT_INTERVAL: Duration = Duration::from_secs(30);
+// rounds shown in the bracket, the older ones don't fit in an embed
+const ROUNDS_SHOWN: usize = 6;
+// characters of the bracket embed given to the rounds and to the swiss standings
+const ROUNDS_BUDGET: usize = 3000;
+const STANDINGS_BUDGET: usize = 2000;
+
+static TOURNAMENT_SCHEDULER_STARTED: AtomicBool = AtomicBool::new(false);
+// the scheduler and the end of a duel both edit tournaments, one at a time
+static TOURNAMENT_LOCK: Mutex<()> = Mutex::const_new(());
+
+async fn show_help() -> CreateMessage {
+  let embed = CreateEmbed::new()
+    .title("Usage of `tournament`")
+    .description(
+      "`~tournament create <single / double / swiss>` (open the sign-ups of a tournament in this channel)\n
+      `~tournament join` / `~tournament leave`\n
+      `~tournament start` (the organizer closes the sign-ups, players are seeded by rating)\n
+      `~tournament bracket`\n
+      `~tournament cancel` (the organizer stops the tournament)\n
+      Every pairing is played as a duel in this channel, a duel nobody wins is played again",
+    )
+    .color(Colour::DARK_GREEN);
+  CreateMessage::new().embed(embed)
+}
+
+fn get_match_string(current: &TournamentMatch) -> String {
+  let players = current
+    .players
+    .iter()
+    .map(|player| format!("<@{player}>"))
+    .collect::<Vec<_>>()
+    .join(" vs ");
+  match (&current.winner, current.is_bye()) {
+    (_, true) => format!("{players} (bye)"),
+    (Some(winner), false) => format!("{players} → <@{winner}>"),
+    (None, false) if current.duel_id.is_some() => format!("{players} (playing)"),
+    (None, false) => format!("{players} (waiting)"),
+  }
+}
+
+fn create_bracket_embed(tournament: &Tournament) -> CreateEmbed {
+  let mut embed = CreateEmbed::new()
+    .title(format!(
+      "{format} tournament #{id}",
+      format = tournament.format.name(),
+      id = tournament.tournament_id
+    ))
+    .colour(if tournament.is_finished() {
+      Colour::GOLD
+    } else {
+      Colour::TEAL
+    })
+    .timestamp(Timestamp::now());
+  if !tournament.is_started() {
+    let players = tournament
+      .participants
+      .iter()
+      .map(|player| format!("<@{player}>"))
+      .collect::<Vec<_>>();
+    return embed.description(format!(
+      "Sign-ups are open, type `~tournament join`\n{count} player(s): {players}",
+      count = players.len(),
+      players = players.join(" ")
+    ));
+  }
+  let first_round = tournament.round.saturating_sub(ROUNDS_SHOWN as u32) + 1;
+  let round_budget = ROUNDS_BUDGET / (tournament.round - first_round + 1) as usize;
+  for round in first_round..=tournament.round {
+    let matches = tournament
+      .matches
+      .iter()
+      .filter(|current| current.round == round)
+      .map(get_match_string)
+      .collect::<Vec<_>>();
+    embed = add_line_fields(embed, &format!("Round {round}"), &matches, round_budget);
+  }
+  if tournament.format == TournamentFormat::Swiss {
+    let standings = tournament
+      .ranking()
+      .iter()
+      .enumerate()
+      .map(|(rank, player)| {
+        format!(
+          "`{rank}.` <@{player}> {wins}-{losses}",
+          rank = rank + 1,
+          wins = tournament.wins(player),
+          losses = tournament.losses(player)
+        )
+      })
+      .collect::<Vec<_>>();
+    embed = add_line_fields(embed, "Standings", &standings, STANDINGS_BUDGET);
+  }
+  match &tournament.winner {
+    Some(winner) => embed.description(format!(":trophy: <@{winner}> won the tournament!")),
+    None => embed.description(format!(
+      "Round {round}, {alive} player(s) left",
+      round = tournament.round,
+      alive = tournament.alive().len()
+    )),
+  }
+}
+
+async fn send_bracket(ctx: &Context, tournament: &Tournament) {
+  let message = CreateMessage::new().embed(create_bracket_embed(tournament));
+  if let Err(why) = tournament.channel_id.send_message(&ctx.http, message).await {
+    error!("Can't post the bracket: {:?}", why);
+  }
+}
+
+async fn get_tournament(ctx: &Context, tournament_id: usize) -> Option<Tournament> {
+  let data = get_data(ctx).await.ok()?;
+  data
+    .tournaments
+    .into_iter()
+    .find(|tournament| tournament.tournament_id == tournament_id)
+}
+
+// the tournament of the channel that isn't over yet
+async fn get_channel_tournament(ctx: &Context, channel_id: ChannelId) -> Option<Tournament> {
+  let data = get_data(ctx).await.ok()?;
+  data
+    .tournaments
+    .into_iter()
+    .find(|tournament| tournament.channel_id == channel_id && !tournament.is_finished())
+}
+
+// replace the stored tournament, a tournament cancelled in the meantime stays cancelled
+async fn save_tournament(ctx: &Context, tournament: Tournament) {
+  edit_data(ctx, |data| {
+    if let Some(current) = data
+      .tournaments
+      .iter_mut()
+      .find(|current| current.tournament_id == tournament.tournament_id)
+    {
+      *current = tournament;
+    }
+  })
+  .await;
+}
+
+// play a pairing as a duel once both players are free
+async fn start_tournament_match(
+  ctx: &Context,
+  tournament: &Tournament,
+  index: usize,
+) -> Result<Option<usize>, String> {
+  let pairing = &tournament.matches[index];
+  let mut users = Vec::new();
+  for player in pairing.players.iter() {
+    let user = find_user_in_data(ctx, player).await?;
+    if user.duel_id.is_some() {
+      return Ok(None);
+    }
+    users.push(user);
+  }
+  let pool = get_problem_pool(ctx, tournament.guild_id, None).await;
+  let problem_set = get_pool_problemset(&pool).await?;
+  let user_submissions = get_all_user_submissions(&users).await;
+  let rating = get_average_rating(&users).await;
+  let problem = get_problem_for_users(
+    &users,
+    rating,
+    &problem_set,
+    &user_submissions,
+    &ProblemFilter::default(),
+  )
+  .await
+  .ok_or("We can't provide a problem".to_owned())?;
+  let builder = create_problem_message(
+    &problem,
+    format!(
+      "Tournament #{id}, round {round}: {players}\nType `~match finish` once you have solved the problem!",
+      id = tournament.tournament_id,
+      round = pairing.round,
+      players = pairing
+        .players
+        .iter()
+        .map(|player| format!("<@{player}>"))
+        .collect::<Vec<_>>()
+        .join(" vs ")
+    ),
+    true,
+  )
+  .ok_or("We can't provide a problem".to_owned())?;
+  let message = tournament
+    .channel_id
+    .send_message(&ctx.http, builder)
+    .await
+    .map_err(|why| why.to_string())?;
+  let duel = create_match(
+    ctx,
+    Duel {
+      duel_id: 0,
+      players: users,
+      begin_time: SystemTime::now(),
+      problems: Vec::from([problem]),
+      channel_id: message,
+      duel_type: DuelType::DUEL,
+      score_distribution: None,
+      match_duration: None,
+      problems_point: None,
+      contest: None,
+      scoring: None,
+      teams: None,
+      tournament_id: Some(tournament.tournament_id),
+      attempts: None,
+      wrong_penalty: None,
+      claimed_points: None,
+      claimed_at: None,
+      gave_up: Vec::new(),
+      end_time: None,
+      paused_at: None,
+      pauses: Vec::new(),
+    },
+  )
+  .await;
+  single_duel_interactor(ctx, duel.clone()).await;
+  Ok(Some(duel.duel_id))
+}
+
+/*
+  Open the next round once every pairing of the current one has a winner, then start the duels
+  of the pairings whose players are free
+*/
+async fn advance_tournament(ctx: &Context, tournament_id: usize) {
+  let _lock = TOURNAMENT_LOCK.lock().await;
+  let mut tournament = match get_tournament(ctx, tournament_id).await {
+    Some(tournament) if tournament.is_started() && !tournament.is_finished() => tournament,
+    _ => return,
+  };
+  if tournament.is_round_over() {
+    tournament.next_round();
+    if tournament.is_finished() {
+      info!("Tournament {} is over", tournament_id);
+    }
+    save_tournament(ctx, tournament.clone()).await;
+    send_bracket(ctx, &tournament).await;
+  }
+  for index in 0..tournament.matches.len() {
+    let pairing = &tournament.matches[index];
+    if pairing.winner.is_some() || pairing.duel_id.is_some() {
+      continue;
+    }
+    match start_tournament_match(ctx, &tournament, index).await {
+      Ok(Some(duel_id)) => {
+        tournament.matches[index].duel_id = Some(duel_id);
+        save_tournament(ctx, tournament.clone()).await;
+      }
+      Ok(None) => (),
+      Err(why) => error!(
+        "Can't start a duel of tournament {}: {}",
+        tournament_id, why
+      ),
+    }
+  }
+}
+
+// the scheduler picks the result up and starts what comes next
+pub async fn record_tournament_result(
+  ctx: &Context,
+  tournament_id: usize,
+  duel_id: usize,
+  winner: Option<String>,
+) {
+  let _lock = TOURNAMENT_LOCK.lock().await;
+  let mut tournament = match get_tournament(ctx, tournament_id).await {
+    Some(tournament) => tournament,
+    None => return,
+  };
+  if tournament.record_result(duel_id, winner) {
+    save_tournament(ctx, tournament).await;
+  }
+}
+
+pub async fn tournament_interactor(ctx: &Context) {
+  if TOURNAMENT_SCHEDULER_STARTED.swap(true, Ordering::SeqCst) {
+    return;
+  }
+  let ctx = ctx.clone();
+  tokio::spawn(async move {
+    loop {
+      if let Ok(data) = get_data(&ctx).await {
+        for tournament in data.tournaments.iter() {
+          advance_tournament(&ctx, tournament.tournament_id).await;
+        }
+      }
+      sleep(TOURNAMENT_INTERVAL).await;
+    }
+  });
+}
+
+// the players sorted by codeforces rating, unrated ones last
+async fn seed_players(ctx: &Context, participants: &[String]) -> Vec<String> {
+  let mut seeded = Vec::new();
+  for player in participants.iter() {
+    let rating = match find_user_in_data(ctx, player).await {
+      Ok(user) => get_user_rating(&user.handle).await.unwrap_or_default(),
+      Err(_) => 0,
+    };
+    seeded.push((rating, player.clone()));
+  }
+  seeded.sort_by_key(|(rating, _)| std::cmp::Reverse(*rating));
+  seeded.into_iter().map(|(_, player)| player).collect()
+}
+
+#[command]
+pub async fn tournament(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+  let option = args.single::<String>().unwrap_or_default();
+  let user_id = msg.author.id.to_string();
+  // read when needed, some options hold the lock first
+  macro_rules! get_current {
+    () => {
+      match get_channel_tournament(ctx, msg.channel_id).await {
+        Some(tournament) => tournament,
+        None => {
+          error_response!(
+            ctx,
+            msg,
+            "There is no tournament in this channel, try `~tournament create`".to_owned()
+          );
+          return Ok(());
+        }
+      }
+    };
+  }
+  match option.as_str() {
+    "create" => {
+      let format = match TournamentFormat::parse(&args.single::<String>().unwrap_or_default()) {
+        Some(format) => format,
+        None => {
+          error_response!(
+            ctx,
+            msg,
+            "Please choose `single`, `double` or `swiss`".to_owned()
+          );
+          return Ok(());
+        }
+      };
+      if get_channel_tournament(ctx, msg.channel_id).await.is_some() {
+        error_response!(
+          ctx,
+          msg,
+          "There is already a tournament in this channel".to_owned()
+        );
+        return Ok(());
+      }
+      if let Err(why) = find_user_in_data(ctx, &user_id).await {
+        error_response!(ctx, msg, why);
+        return Ok(());
+      }
+      let tournament_id = get_data(ctx)
+        .await
+        .ok()
+        .and_then(|data| {
+          data
+            .tournaments
+            .iter()
+            .map(|tournament| tournament.tournament_id + 1)
+            .max()
+        })
+        .unwrap_or_default();
+      let tournament = Tournament {
+        tournament_id,
+        guild_id: msg.guild_id,
+        channel_id: msg.channel_id,
+        organizer: user_id.clone(),
+        format,
+        participants: Vec::from([user_id]),
+        round: 0,
+        matches: Vec::new(),
+        winner: None,
+      };
+      edit_data(ctx, |data| data.tournaments.push(tournament.clone())).await;
+      send_bracket(ctx, &tournament).await;
+    }
+    "join" | "leave" => {
+      // read under the lock, the sign-ups of others or the start would otherwise be lost
+      let _lock = TOURNAMENT_LOCK.lock().await;
+      let mut tournament = get_current!();
+      if tournament.is_started() {
+        error_response!(ctx, msg, "The tournament has already started".to_owned());
+        return Ok(());
+      }
+      let joined = tournament.participants.contains(&user_id);
+      if option == "join" {
+        if let Err(why) = find_user_in_data(ctx, &user_id).await {
+          error_response!(ctx, msg, why);
+          return Ok(());
+        }
+        if joined {
+          error_response!(ctx, msg, "You have already joined".to_owned());
+          return Ok(());
+        }
+        tournament.participants.push(user_id);
+      } else {
+        if !joined {
+          error_response!(ctx, msg, "You haven't joined the tournament".to_owned());
+          return Ok(());
+        }
+        tournament.participants.retain(|player| *player != user_id);
+      }
+      save_tournament(ctx, tournament.clone()).await;
+      send_bracket(ctx, &tournament).await;
+    }
+    "start" => {
+      let lock = TOURNAMENT_LOCK.lock().await;
+      let mut tournament = get_current!();
+      if tournament.organizer != user_id {
+        error_response!(
+          ctx,
+          msg,
+          "Only the organizer can start the tournament".to_owned()
+        );
+        return Ok(());
+      }
+      if tournament.is_started() {
+        error_response!(ctx, msg, "The tournament has already started".to_owned());
+        return Ok(());
+      }
+      if tournament.participants.len() < 2 {
+        error_response!(ctx, msg, "A tournament needs at least 2 players".to_owned());
+        return Ok(());
+      }
+      tournament.participants = seed_players(ctx, &tournament.participants).await;
+      tournament.next_round();
+      save_tournament(ctx, tournament.clone()).await;
+      drop(lock);
+      send_bracket(ctx, &tournament).await;
+      advance_tournament(ctx, tournament.tournament_id).await;
+    }
+    "bracket" => {
+      let tournament = get_current!();
+      send_bracket(ctx, &tournament).await;
+    }
+    "cancel" => {
+      let tournament = get_current!();
+      if tournament.organizer != user_id {
+        error_response!(
+          ctx,
+          msg,
+          "Only the organizer can cancel the tournament".to_owned()
+        );
+        return Ok(());
+      }
+      let tournament_id = tournament.tournament_id;
+      // a round being advanced would otherwise save the tournament back
+      let _lock = TOURNAMENT_LOCK.lock().await;
+      edit_data(ctx, |data| {
+        data
+          .tournaments
+          .retain(|current| current.tournament_id != tournament_id)
+      })
+      .await;
+      let embed = CreateEmbed::new()
+        .description(format!("Tournament #{tournament_id} has been cancelled"))
+        .colour(Colour::DARK_GREEN);
+      msg
+        .channel_id
+        .send_message(&ctx.http, CreateMessage::new().embed(embed))
+        .await?;
+    }
+    _ => {
+      msg
+        .channel_id
+        .send_message(&ctx.http, show_help().await)
+        .await?;
+    }
+  }
+  Ok(())
+}
@@ -0,0 +1,320 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serenity::builder::{CreateEmbed, CreateEmbedFooter, CreateMessage};
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::{Args, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use tokio::time::{sleep, Duration};
+use tracing::info;
+
+use crate::commands::giveme::*;
+use crate::commands::handle::*;
+use crate::commands::standings::*;
+
+use crate::core::data::User;
+use crate::core::data::*;
+use crate::error_response;
+use crate::utils::message_creator::*;
+
+const TRACK_INTERVAL: Duration = Duration::from_secs(60);
+const LIVE_ROWS: usize = 20;
+
+static TRACKER_STARTED: AtomicBool = AtomicBool::new(false);
+
+async fn show_help() -> CreateMessage {
+  let embed = CreateEmbed::new()
+    .title("Usage of `track`")
+    .description(
+      "`~track <contest id>` (keep a live standings of the registered members in this channel until the round is over)\n
+      `~track stop <contest id>`",
+    )
+    .color(Colour::DARK_GREEN);
+  CreateMessage::new().embed(embed)
+}
+
+// the members of a party, a team and a lone contestant alike
+fn party_key(party: &Author) -> String {
+  party
+    .members
+    .iter()
+    .map(|member| member.handle.clone())
+    .collect::<Vec<_>>()
+    .join(", ")
+}
+
+// (party, problem index, seconds since the start) of every accepted problem
+fn get_solves(
+  results: &ContestResults,
+  ranked: &[(usize, RanklistRow)],
+) -> Vec<(String, String, u64)> {
+  let mut solves = Vec::new();
+  for (_, row) in ranked.iter() {
+    for (problem, result) in results.problems.iter().zip(row.problemResults.iter()) {
+      if result.points > 0f64 {
+        solves.push((
+          party_key(&row.party),
+          problem.index.clone(),
+          result.bestSubmissionTimeSeconds.unwrap_or_default(),
+        ));
+      }
+    }
+  }
+  solves.sort_by_key(|(_, _, time)| *time);
+  solves
+}
+
+fn create_live_embed(
+  results: &ContestResults,
+  ranked: &[(usize, RanklistRow)],
+  users: &[User],
+) -> CreateEmbed {
+  let finished = results.contest.phase == "FINISHED";
+  let rows = ranked
+    .iter()
+    .take(LIVE_ROWS)
+    .map(|(rank, row)| get_row_string(*rank, row, results, users))
+    .collect::<Vec<_>>();
+  let end = results.contest.startTimeSeconds.unwrap_or_default() + results.contest.durationSeconds;
+  CreateEmbed::new()
+    .title(format!("Live standings: {}", results.contest.name))
+    .url(format!(
+      "https://codeforces.com/contest/{id}/standings",
+      id = results.contest.id
+    ))
+    .description(if rows.is_empty() {
+      "No registered member has submitted yet".to_owned()
+    } else {
+      rows.join("\n")
+    })
+    .field(
+      if finished { "Ended" } else { "Ends" },
+      format!("<t:{end}:R>"),
+      false,
+    )
+    .colour(if finished { Colour::GOLD } else { Colour::TEAL })
+    .footer(CreateEmbedFooter::new(if finished {
+      "Final standings".to_owned()
+    } else {
+      format!("Updated every {} second(s)", TRACK_INTERVAL.as_secs())
+    }))
+    .timestamp(Timestamp::now())
+}
+
+async fn get_upcoming_round(contest_id: u32) -> Option<Contest> {
+  get_contests(false)
+    .await
+    .ok()?
+    .into_iter()
+    .find(|contest| contest.id == contest_id && contest.phase == "BEFORE")
+}
+
+fn create_waiting_embed(contest: &Contest) -> CreateEmbed {
+  let start = contest.startTimeSeconds.unwrap_or_default();
+  CreateEmbed::new()
+    .title(format!("Live standings: {}", contest.name))
+    .url(format!(
+      "https://codeforces.com/contest/{id}/standings",
+      id = contest.id
+    ))
+    .description("The round hasn't started yet")
+    .field("Starts", format!("<t:{start}:R>"), false)
+    .colour(Colour::TEAL)
+    .timestamp(Timestamp::now())
+}
+
+fn create_solve_message(party: &String, index: &String, time: u64) -> CreateMessage {
+  let (_, minutes, hours) = convert_to_hms(&Duration::from_secs(time));
+  let embed = CreateEmbed::new()
+    .description(format!(
+      "{party} solved **{index}** at {hours:0>2}:{minutes:0>2}"
+    ))
+    .colour(Colour::DARK_GREEN);
+  CreateMessage::new().embed(embed)
+}
+
+/*
+  Announce the problems solved since the last update, edit the live standings and stop tracking
+  once the round is over
+*/
+async fn update_tracker(ctx: &Context, tracker: &ContestTracker, users: &[User]) {
+  let (results, ranked) = match get_member_standing(users, tracker.contest_id).await {
+    Ok(standing) => standing,
+    // the standings aren't available before the start, try again later
+    Err(_) => return,
+  };
+  let channel_id = tracker.message.channel_id;
+  let mut solved = tracker.solved.clone();
+  for (party, index, time) in get_solves(&results, &ranked) {
+    if solved.contains(&(party.clone(), index.clone())) {
+      continue;
+    }
+    let _ = channel_id
+      .send_message(&ctx.http, create_solve_message(&party, &index, time))
+      .await;
+    solved.push((party, index));
+  }
+  edit_to_message(
+    ctx,
+    create_live_embed(&results, &ranked, users),
+    tracker.message.clone(),
+  )
+  .await;
+
+  let finished = results.contest.phase == "FINISHED";
+  if finished {
+    info!("Contest {} is over, stop tracking it", tracker.contest_id);
+  }
+  let message_id = tracker.message.id;
+  edit_data(ctx, |data| {
+    if finished {
+      data
+        .trackers
+        .retain(|current| current.message.id != message_id);
+    } else if let Some(current) = data
+      .trackers
+      .iter_mut()
+      .find(|current| current.message.id == message_id)
+    {
+      current.solved = solved;
+    }
+  })
+  .await;
+}
+
+pub async fn tracker_interactor(ctx: &Context) {
+  if TRACKER_STARTED.swap(true, Ordering::SeqCst) {
+    return;
+  }
+  let ctx = ctx.clone();
+  tokio::spawn(async move {
+    loop {
+      if let Ok(data) = get_data(&ctx).await {
+        for tracker in data.trackers.iter() {
+          let users = keep_guild_members(&ctx, tracker.guild_id, data.data.clone()).await;
+          update_tracker(&ctx, tracker, &users).await;
+        }
+      }
+      sleep(TRACK_INTERVAL).await;
+    }
+  });
+}
+
+#[command]
+pub async fn track(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+  let option = args.single::<String>().unwrap_or_default();
+  let (stop, contest_id) = match option.as_str() {
+    "stop" => (true, args.single::<u32>().ok()),
+    option => (false, option.parse::<u32>().ok()),
+  };
+  let contest_id = match contest_id {
+    Some(contest_id) => contest_id,
+    None => {
+      msg
+        .channel_id
+        .send_message(&ctx.http, show_help().await)
+        .await?;
+      return Ok(());
+    }
+  };
+  let data = match get_data(ctx).await {
+    Ok(data) => data,
+    Err(why) => {
+      error_response!(ctx, msg, why);
+      return Ok(());
+    }
+  };
+  let tracked = data.trackers.iter().any(|tracker| {
+    tracker.contest_id == contest_id && tracker.message.channel_id == msg.channel_id
+  });
+
+  if stop {
+    if !tracked {
+      error_response!(
+        ctx,
+        msg,
+        format!("Contest `{contest_id}` isn't tracked in this channel")
+      );
+      return Ok(());
+    }
+    let channel_id = msg.channel_id;
+    edit_data(ctx, |data| {
+      data.trackers.retain(|tracker| {
+        tracker.contest_id != contest_id || tracker.message.channel_id != channel_id
+      })
+    })
+    .await;
+    let embed = CreateEmbed::new()
+      .description(format!("Stopped tracking contest `{contest_id}`"))
+      .colour(Colour::DARK_GREEN);
+    msg
+      .channel_id
+      .send_message(&ctx.http, CreateMessage::new().embed(embed))
+      .await?;
+    return Ok(());
+  }
+
+  if tracked {
+    error_response!(
+      ctx,
+      msg,
+      format!("Contest `{contest_id}` is already tracked in this channel")
+    );
+    return Ok(());
+  }
+  let builder = create_await_message();
+  let message = msg.channel_id.send_message(&ctx.http, builder).await?;
+  let users = keep_guild_members(ctx, msg.guild_id, data.data).await;
+  let (results, ranked) = match get_member_standing(&users, contest_id).await {
+    Ok(standing) => standing,
+    Err(why) => {
+      // the standings of a round show up once it starts, the scheduler picks it up then
+      match get_upcoming_round(contest_id).await {
+        Some(contest) => {
+          edit_to_message(ctx, create_waiting_embed(&contest), message.clone()).await;
+          let tracker = ContestTracker {
+            contest_id,
+            message,
+            solved: Vec::new(),
+            guild_id: msg.guild_id,
+          };
+          edit_data(ctx, |data| data.trackers.push(tracker)).await;
+        }
+        None => {
+          edit_to_failed_status(ctx, message).await;
+          error_response!(ctx, msg, why);
+        }
+      }
+      return Ok(());
+    }
+  };
+  if results.contest.phase == "FINISHED" {
+    edit_to_failed_status(ctx, message).await;
+    error_response!(
+      ctx,
+      msg,
+      format!("Contest `{contest_id}` is over, use `~standings {contest_id}` instead")
+    );
+    return Ok(());
+  }
+  // what was solved before the tracking started isn't announced
+  let solved = get_solves(&results, &ranked)
+    .into_iter()
+    .map(|(party, index, _)| (party, index))
+    .collect::<Vec<_>>();
+  edit_to_message(
+    ctx,
+    create_live_embed(&results, &ranked, &users),
+    message.clone(),
+  )
+  .await;
+  let tracker = ContestTracker {
+    contest_id,
+    message,
+    solved,
+    guild_id: msg.guild_id,
+  };
+  edit_data(ctx, |data| data.trackers.push(tracker)).await;
+  Ok(())
+}
@@ -0,0 +1,196 @@
+use std::collections::HashSet;
+
+use serenity::builder::{CreateEmbed, CreateEmbedFooter, CreateMessage};
+use serenity::collector::MessageCollector;
+use serenity::futures::StreamExt;
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use tokio::time::Duration;
+
+use crate::commands::duel::*;
+use crate::commands::giveme::*;
+use crate::commands::handle::*;
+use crate::commands::lockout::*;
+
+use crate::core::data::User;
+use crate::core::data::*;
+use crate::core::pool::ProblemPool;
+use crate::error_response;
+use crate::utils::message_creator::*;
+
+const VETO_DURATION: Duration = Duration::from_secs(60);
+pub const DEFAULT_MAX_REROLLS: u32 = 2;
+
+fn create_veto_embed(problems: &[Problem], rerolls_left: u32) -> CreateEmbed {
+  let problem_list = problems
+    .iter()
+    .enumerate()
+    .map(|(i, problem)| {
+      format!(
+        "`{number}.` {link} ({rating})",
+        number = i + 1,
+        link = create_href(problem_url(problem), &problem.name),
+        rating = problem.difficulty_string().unwrap_or_default()
+      )
+    })
+    .collect::<Vec<_>>()
+    .join("\n");
+  CreateEmbed::new()
+    .title("Problem veto")
+    .description(format!(
+      "{problem_list}\n\n`~match veto <number>` replaces a problem right away, every player has one veto\n
+      `~match reroll <number>` replaces it once most players asked for it\n
+      `~match ready` starts the match once everyone is ready\n
+      The match starts in {seconds} seconds anyway",
+      seconds = VETO_DURATION.as_secs()
+    ))
+    .footer(CreateEmbedFooter::new(format!(
+      "Rerolls left: {rerolls_left}"
+    )))
+    .colour(Colour::TEAL)
+}
+
+// a problem with the same rating as `problem` that nobody has seen in this veto yet
+async fn find_replacement(
+  users: &Vec<User>,
+  problem: &Problem,
+  seen: &[Problem],
+  pool: &ProblemPool,
+  filter: &ProblemFilter,
+) -> Option<Problem> {
+  let problem_set = get_pool_problemset(pool)
+    .await
+    .ok()?
+    .into_iter()
+    .filter(|candidate| !seen.contains(candidate))
+    .collect::<Vec<_>>();
+  let user_submissions = get_all_user_submissions(users).await;
+  let rating = problem.difficulty()? as u32;
+  get_problem_for_users(users, rating, &problem_set, &user_submissions, filter).await
+}
+
+// `~match veto 2` into the index of the problem
+fn parse_problem_number(content: &str, command: &str, problem_count: usize) -> Option<usize> {
+  let rest = content.strip_prefix(command)?.trim();
+  if rest.is_empty() && problem_count == 1 {
+    return Some(0);
+  }
+  let number = rest.parse::<usize>().ok()?;
+  (1..=problem_count).contains(&number).then(|| number - 1)
+}
+
+/*
+  Before a match starts the players may veto a problem, which replaces it right away, or vote to
+  reroll it, which replaces it once more than half of them voted. Replacements have the same rating
+  and the number of replacements per match is limited by the configuration of the server
+*/
+pub async fn veto_problems(
+  ctx: &Context,
+  msg: &Message,
+  users: &Vec<User>,
+  problems: &mut [Problem],
+  pool: &ProblemPool,
+  filter: &ProblemFilter,
+) {
+  let config = get_guild_config(ctx, msg.guild_id).await;
+  let mut rerolls_left = config.max_rerolls.unwrap_or(DEFAULT_MAX_REROLLS);
+  if rerolls_left == 0 {
+    return;
+  }
+  let _ = msg
+    .channel_id
+    .send_message(
+      &ctx.http,
+      CreateMessage::new().embed(create_veto_embed(problems, rerolls_left)),
+    )
+    .await;
+
+  let mut seen = problems.to_vec();
+  let mut vetoed: HashSet<UserId> = HashSet::new();
+  let mut ready: HashSet<UserId> = HashSet::new();
+  let mut votes: Vec<HashSet<UserId>> = vec![HashSet::new(); problems.len()];
+  let mut message_collector = MessageCollector::new(&ctx.shard)
+    .channel_id(msg.channel_id)
+    .timeout(VETO_DURATION)
+    .stream();
+  while let Some(message) = message_collector.next().await {
+    let author = message.author.id;
+    if !users.iter().any(|user| user.userId == author.to_string()) {
+      continue;
+    }
+    let content = message.content.trim();
+    if content == "~match ready" {
+      ready.insert(author);
+      if ready.len() == users.len() {
+        break;
+      }
+      continue;
+    }
+    let wants_replacement =
+      content.starts_with("~match veto") || content.starts_with("~match reroll");
+    if wants_replacement && rerolls_left == 0 {
+      error_response!(ctx, message, "There are no rerolls left".to_owned());
+      continue;
+    }
+
+    let vetoing = content.starts_with("~match veto");
+    let index = if let Some(index) = parse_problem_number(content, "~match veto", problems.len()) {
+      if vetoed.contains(&author) {
+        error_response!(ctx, message, "You already used your veto".to_owned());
+        continue;
+      }
+      index
+    } else if let Some(index) = parse_problem_number(content, "~match reroll", problems.len()) {
+      votes[index].insert(author);
+      if votes[index].len() * 2 <= users.len() {
+        let _ = message
+          .channel_id
+          .say(
+            &ctx.http,
+            format!(
+              "{} of {} players want to reroll problem {}",
+              votes[index].len(),
+              users.len(),
+              index + 1
+            ),
+          )
+          .await;
+        continue;
+      }
+      index
+    } else {
+      continue;
+    };
+
+    match find_replacement(users, &problems[index], &seen, pool, filter).await {
+      Some(replacement) => {
+        // a veto is only used up once it replaced the problem
+        if vetoing {
+          vetoed.insert(author);
+        }
+        seen.push(replacement.clone());
+        problems[index] = replacement;
+        votes[index].clear();
+        rerolls_left -= 1;
+        let _ = msg
+          .channel_id
+          .send_message(
+            &ctx.http,
+            CreateMessage::new().embed(create_veto_embed(problems, rerolls_left)),
+          )
+          .await;
+      }
+      None => {
+        error_response!(
+          ctx,
+          message,
+          format!(
+            "There is no other problem with the rating of problem {}",
+            index + 1
+          )
+        );
+      }
+    }
+  }
+}
@@ -0,0 +1,352 @@
+use std::collections::HashSet;
+use std::time::SystemTime;
+
+use rand::seq::SliceRandom;
+
+use serenity::builder::{CreateEmbed, CreateMessage};
+use serenity::collector::MessageCollector;
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::{Args, CommandResult, Delimiter};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use tokio::time::Duration;
+
+use crate::commands::duel::*;
+use crate::commands::giveme::*;
+use crate::commands::handle::*;
+use crate::commands::lockout::*;
+use crate::commands::match_control::*;
+
+use crate::core::data::User;
+use crate::core::data::*;
+use crate::core::pool::ProblemPool;
+use crate::core::scoring::*;
+use crate::error_response;
+use crate::utils::message_creator::*;
+
+const WAIT_DURATION: Duration = Duration::from_millis(1000 * 30);
+// points of a problem when the contest doesn't say
+const DEFAULT_PROBLEM_POINTS: u32 = 500;
+
+async fn show_help() -> CreateMessage {
+  let embed = CreateEmbed::new()
+    .title("Usage of `virtual`")
+    .description(
+      "`~virtual <contest id / random> <@user> [pool=div2,edu,... (optional)]` (take part in a past codeforces contest together, `random` picks one nobody in the group has touched)\n
+      During the contest type `~match update` to see the scoreboard or `~match giveup` to leave\n
+      `~match draw` / `~match extend <minutes>` (ends the match or adds time once every player asked for it)\n
+      `~match pause` / `~match resume` (freeze the clock, e.g. while Codeforces is down)",
+    )
+    .color(Colour::DARK_GREEN);
+  CreateMessage::new().embed(embed)
+}
+
+// ids of the contests any of the users has submitted to
+fn get_touched_contests(user_submissions: &[Vec<Submission>]) -> HashSet<u32> {
+  user_submissions
+    .iter()
+    .flatten()
+    .filter_map(|submission| submission.contestId)
+    .collect()
+}
+
+async fn pick_random_contest(pool: &ProblemPool, touched: &HashSet<u32>) -> Result<u32, String> {
+  let contests = get_contests(false).await?;
+  let untouched = contests
+    .iter()
+    .filter(|contest| {
+      contest.phase == "FINISHED" && pool.contains(contest) && !touched.contains(&contest.id)
+    })
+    .map(|contest| contest.id)
+    .collect::<Vec<_>>();
+  untouched
+    .choose(&mut rand::thread_rng())
+    .copied()
+    .ok_or(format!(
+      "Every contest in the pool `{}` was touched by someone in the group",
+      pool.describe()
+    ))
+}
+
+/*
+  The contest with its problems, making sure nobody in the group has submitted to it or taken
+  part in it
+*/
+async fn prepare_contest(
+  users: &[User],
+  contest_id: u32,
+  touched: &HashSet<u32>,
+) -> Result<ContestResults, String> {
+  if touched.contains(&contest_id) {
+    return Err(format!(
+      "Someone in the group has already submitted to contest `{contest_id}`"
+    ));
+  }
+  let handles = users
+    .iter()
+    .map(|user| user.handle.clone())
+    .collect::<Vec<_>>();
+  let results = get_contest_standing_of_handles(contest_id, &handles).await?;
+  if !results.rows.is_empty() {
+    return Err(format!(
+      "Someone in the group has already taken part in contest `{contest_id}`"
+    ));
+  }
+  if results.contest.phase != "FINISHED" {
+    return Err(format!("Contest `{contest_id}` isn't over yet"));
+  }
+  if results.problems.is_empty() {
+    return Err(format!("Contest `{contest_id}` has no problems"));
+  }
+  Ok(results)
+}
+
+pub fn get_virtual_scoreboard(
+  duel: &Duel,
+  user_submissions: &[Vec<Submission>],
+) -> Vec<ScoreboardRow> {
+  let (begin, end) = get_lockout_window(duel);
+  let max_points = duel
+    .problems_point
+    .clone()
+    .unwrap_or_default()
+    .iter()
+    .map(|points| *points as f64)
+    .collect::<Vec<_>>();
+  let mut attempts = get_attempts(&duel.problems, user_submissions, begin, end);
+  // the time spent paused doesn't add to the penalty
+  for attempt in attempts.iter_mut().flatten() {
+    attempt.solved_at = attempt
+      .solved_at
+      .map(|solved_at| duel.get_running_seconds(solved_at));
+  }
+  rank_attempts(
+    duel.scoring.unwrap_or(ScoringStyle::Icpc),
+    &attempts,
+    &max_points,
+  )
+}
+
+async fn send_scoreboard(ctx: &Context, channel_id: ChannelId, duel: &Duel) {
+  let builder = create_await_message();
+  let message = match channel_id.send_message(&ctx.http, builder).await {
+    Ok(message) => message,
+    Err(_) => return,
+  };
+  let user_submissions = get_all_user_submissions(&duel.players).await;
+  let rows = get_virtual_scoreboard(duel, &user_submissions);
+  edit_to_message(ctx, create_scoreboard_embed(duel, &rows), message).await;
+}
+
+pub async fn single_virtual_interactor(ctx: &Context, mut duel: Duel) {
+  let msg = duel.channel_id.clone();
+  let ctx_1 = ctx.clone();
+  tokio::spawn(async move {
+    let mut votes = MatchVotes::default();
+    let mut message_collector = MessageCollector::new(&ctx_1.shard)
+      .channel_id(msg.channel_id)
+      .stream();
+    // the clock only runs out while the contest isn't paused
+    while let Some(message) = next_match_message(&duel, &mut message_collector).await {
+      let user_id = message.author.id.to_string();
+      if !duel.players.iter().any(|player| player.userId == user_id) {
+        continue;
+      }
+      if is_match_control(&message.content) {
+        let voters = duel
+          .players
+          .iter()
+          .map(|player| player.userId.clone())
+          .collect::<Vec<_>>();
+        if handle_match_control(&ctx_1, &mut duel, &mut votes, &message, &voters).await {
+          break;
+        }
+        continue;
+      }
+      if message.content != "~match update" && message.content != "~match giveup" {
+        continue;
+      }
+      if message.content == "~match giveup" {
+        duel.remove_user(user_id.clone());
+        leave_duel(&ctx_1, duel.duel_id, &user_id).await;
+        if duel.players.is_empty() {
+          return;
+        }
+        continue;
+      }
+      send_scoreboard(&ctx_1, msg.channel_id, &duel).await;
+    }
+    send_scoreboard(&ctx_1, msg.channel_id, &duel).await;
+    remove_duel(&ctx_1, duel.players).await;
+  });
+}
+
+pub async fn virtual_interactor(ctx: &Context) {
+  let duels = match get_duels(ctx).await {
+    Some(duels) => duels,
+    None => return,
+  };
+  for duel in duels.into_iter() {
+    if duel.duel_type == DuelType::VIRTUAL {
+      single_virtual_interactor(ctx, duel).await;
+    }
+  }
+}
+
+#[command("virtual")]
+pub async fn virtual_contest(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+  let target = match args.single::<String>() {
+    Ok(target) if target != "help" && target != "h" => target,
+    _ => {
+      msg
+        .channel_id
+        .send_message(&ctx.http, show_help().await)
+        .await?;
+      return Ok(());
+    }
+  };
+  let contest_id = match (target.as_str(), target.parse::<u32>()) {
+    ("random", _) => None,
+    (_, Ok(contest_id)) => Some(contest_id),
+    _ => {
+      error_response!(
+        ctx,
+        msg,
+        format!("`{target}` is not a contest id, try `~virtual help`")
+      );
+      return Ok(());
+    }
+  };
+  let rest = Args::new(args.rest(), &[Delimiter::Single(' ')]);
+  let (rest, requested_pool) = match take_pool_arg(&rest) {
+    Ok(parsed) => parsed,
+    Err(why) => {
+      error_response!(ctx, msg, why);
+      return Ok(());
+    }
+  };
+  let pool = get_problem_pool(ctx, msg.guild_id, requested_pool).await;
+  let opponents = match handle_args(
+    ctx,
+    msg,
+    rest,
+    "Invite someone else to the virtual contest".to_owned(),
+    false,
+  )
+  .await
+  {
+    Ok((opponents, _)) => opponents,
+    Err(why) => {
+      error_response!(ctx, msg, why);
+      return Ok(());
+    }
+  };
+  if opponents.is_empty() {
+    error_response!(ctx, msg, "Please invite some registered users".to_owned());
+    return Ok(());
+  }
+
+  msg
+    .channel_id
+    .say(
+      &ctx.http,
+      format!(
+        "<@{user}> invited some users to a virtual contest\n
+    if you want to join, please reponse with ~accept <@{user}> within 30 seconds",
+        user = msg.author.id
+      ),
+    )
+    .await?;
+  let accepted_users = collect_messages(ctx, msg, &opponents, WAIT_DURATION).await;
+  if accepted_users.is_empty() {
+    error_response!(
+      ctx,
+      msg,
+      "The virtual contest has been cancelled because no one accept it".to_owned()
+    );
+    return Ok(());
+  }
+  let users = confirm_user_in_match(ctx, msg, accepted_users).await;
+  if users.len() <= 1 {
+    error_response!(ctx, msg, "No one can join with you :(".to_owned());
+    return Ok(());
+  }
+
+  let builder = create_await_message();
+  let message = msg.channel_id.send_message(&ctx.http, builder).await?;
+  let user_submissions = get_all_user_submissions(&users).await;
+  let touched = get_touched_contests(&user_submissions);
+  let contest_id = match contest_id {
+    Some(contest_id) => contest_id,
+    None => match pick_random_contest(&pool, &touched).await {
+      Ok(contest_id) => contest_id,
+      Err(why) => {
+        edit_to_failed_status(ctx, message).await;
+        error_response!(ctx, msg, why);
+        return Ok(());
+      }
+    },
+  };
+  let results = match prepare_contest(&users, contest_id, &touched).await {
+    Ok(results) => results,
+    Err(why) => {
+      edit_to_failed_status(ctx, message).await;
+      error_response!(ctx, msg, why);
+      return Ok(());
+    }
+  };
+
+  let scoring = if results.contest.r#type == "ICPC" {
+    ScoringStyle::Icpc
+  } else {
+    ScoringStyle::Codeforces
+  };
+  let problems_point = results
+    .problems
+    .iter()
+    .map(|problem| {
+      problem
+        .points
+        .map(|points| points as u32)
+        .unwrap_or(DEFAULT_PROBLEM_POINTS)
+    })
+    .collect::<Vec<_>>();
+  let duel = create_match(
+    ctx,
+    Duel {
+      duel_id: 0,
+      players: users,
+      begin_time: SystemTime::now(),
+      problems: results.problems,
+      channel_id: msg.clone(),
+      duel_type: DuelType::VIRTUAL,
+      score_distribution: None,
+      match_duration: Some(Duration::from_secs(results.contest.durationSeconds)),
+      problems_point: Some(problems_point),
+      contest: Some(results.contest),
+      scoring: Some(scoring),
+      teams: None,
+      tournament_id: None,
+      attempts: None,
+      wrong_penalty: None,
+      claimed_points: None,
+      claimed_at: None,
+      gave_up: Vec::new(),
+      end_time: None,
+      paused_at: None,
+      pauses: Vec::new(),
+    },
+  )
+  .await;
+  edit_to_message(ctx, create_scoreboard_embed(&duel, &[]), message).await;
+  msg
+    .channel_id
+    .say(
+      &ctx.http,
+      "The virtual contest has started! Type `~match update` to see the scoreboard",
+    )
+    .await?;
+  single_virtual_interactor(ctx, duel).await;
+  Ok(())
+}
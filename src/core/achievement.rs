@@ -0,0 +1,204 @@
+use serde::{Deserialize, Serialize};
+
+use serenity::builder::{CreateEmbed, CreateMessage};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use tracing::error;
+
+use crate::commands::giveme::*;
+use crate::commands::handle::*;
+use crate::commands::rating::*;
+
+use crate::core::data::User;
+use crate::core::data::*;
+use crate::utils::time::*;
+
+const DUELS_TO_WIN: u32 = 10;
+const RATING_ABOVE: i32 = 400;
+const STREAK_DAYS: u32 = 30;
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub enum Achievement {
+  FirstChallenge,
+  Duelist,
+  LockoutSweep,
+  Overachiever,
+  Unstoppable,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct UnlockedAchievement {
+  pub achievement: Achievement,
+  pub unlocked_at: u64,
+}
+
+// things that happen in the bot which can unlock an achievement
+#[derive(Clone, Debug)]
+pub enum BotEvent {
+  ChallengeCompleted(Problem),
+  // the problem is `None` when the opponent gave up
+  DuelWon(Option<Problem>),
+  LockoutSwept,
+  StreakUpdated(u32),
+}
+
+impl BotEvent {
+  fn solved_problem(&self) -> Option<&Problem> {
+    match self {
+      BotEvent::ChallengeCompleted(problem) => Some(problem),
+      BotEvent::DuelWon(problem) => problem.as_ref(),
+      _ => None,
+    }
+  }
+}
+
+impl Achievement {
+  pub const ALL: [Achievement; 5] = [
+    Achievement::FirstChallenge,
+    Achievement::Duelist,
+    Achievement::LockoutSweep,
+    Achievement::Overachiever,
+    Achievement::Unstoppable,
+  ];
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      Achievement::FirstChallenge => "First steps",
+      Achievement::Duelist => "Duelist",
+      Achievement::LockoutSweep => "Clean sweep",
+      Achievement::Overachiever => "Overachiever",
+      Achievement::Unstoppable => "Unstoppable",
+    }
+  }
+
+  pub fn emoji(&self) -> &'static str {
+    match self {
+      Achievement::FirstChallenge => ":baby:",
+      Achievement::Duelist => ":crossed_swords:",
+      Achievement::LockoutSweep => ":broom:",
+      Achievement::Overachiever => ":rocket:",
+      Achievement::Unstoppable => ":fire:",
+    }
+  }
+
+  pub fn description(&self) -> String {
+    match self {
+      Achievement::FirstChallenge => "Complete a challenge with `~gotit`".to_owned(),
+      Achievement::Duelist => format!("Win {DUELS_TO_WIN} duels"),
+      Achievement::LockoutSweep => "Claim every problem of a lockout".to_owned(),
+      Achievement::Overachiever => {
+        format!("Solve a problem rated {RATING_ABOVE} above your rating")
+      }
+      Achievement::Unstoppable => format!("Keep a {STREAK_DAYS} day solving streak"),
+    }
+  }
+
+  /*
+    The rule of each achievement, `rating` is the current codeforces rating of the user and is
+    only fetched when the event solved a problem
+  */
+  fn is_unlocked_by(&self, user: &User, event: &BotEvent, rating: Option<u32>) -> bool {
+    match (self, event) {
+      (Achievement::FirstChallenge, BotEvent::ChallengeCompleted(_)) => true,
+      (Achievement::Duelist, BotEvent::DuelWon(_)) => user.duels_won >= DUELS_TO_WIN,
+      (Achievement::LockoutSweep, BotEvent::LockoutSwept) => true,
+      (Achievement::Overachiever, _) => match (event.solved_problem(), rating) {
+        (Some(problem), Some(rating)) if rating > 0 => problem
+          .difficulty()
+          .is_some_and(|difficulty| difficulty >= rating as i32 + RATING_ABOVE),
+        _ => false,
+      },
+      (Achievement::Unstoppable, BotEvent::StreakUpdated(current)) => *current >= STREAK_DAYS,
+      _ => false,
+    }
+  }
+}
+
+impl User {
+  pub fn has_achievement(&self, achievement: Achievement) -> bool {
+    self
+      .achievements
+      .iter()
+      .any(|unlocked| unlocked.achievement == achievement)
+  }
+}
+
+fn create_unlock_message(user: &User, unlocked: &[Achievement]) -> CreateMessage {
+  let badges = unlocked
+    .iter()
+    .map(|achievement| {
+      format!(
+        "{emoji} **{name}**: {description}",
+        emoji = achievement.emoji(),
+        name = achievement.name(),
+        description = achievement.description()
+      )
+    })
+    .collect::<Vec<_>>()
+    .join("\n");
+  let embed = CreateEmbed::new()
+    .title("Achievement unlocked!")
+    .description(badges)
+    .colour(Colour::GOLD)
+    .timestamp(Timestamp::now());
+  CreateMessage::new()
+    .content(format!("<@{id}>", id = user.userId))
+    .embed(embed)
+}
+
+/*
+  Run the rules of the achievements the user doesn't have yet against `event`, store the new
+  ones and announce them in `channel_id` (or by DM when the event didn't happen in a channel)
+*/
+pub async fn trigger_achievements(
+  ctx: &Context,
+  channel_id: Option<ChannelId>,
+  user_id: &String,
+  event: BotEvent,
+) {
+  if let BotEvent::DuelWon(_) = event {
+    edit_user(ctx, user_id, |user| user.duels_won += 1).await;
+  }
+  let user = match find_user_in_data(ctx, user_id).await {
+    Ok(user) => user,
+    Err(_) => return,
+  };
+  let rating = match event.solved_problem() {
+    Some(_) if !user.has_achievement(Achievement::Overachiever) => {
+      get_user_rating(&user.handle).await.ok()
+    }
+    _ => None,
+  };
+  let unlocked = Achievement::ALL
+    .into_iter()
+    .filter(|achievement| {
+      !user.has_achievement(*achievement) && achievement.is_unlocked_by(&user, &event, rating)
+    })
+    .collect::<Vec<_>>();
+  if unlocked.is_empty() {
+    return;
+  }
+
+  let now = unix_now();
+  edit_user(ctx, user_id, |user| {
+    for achievement in unlocked.iter() {
+      user.achievements.push(UnlockedAchievement {
+        achievement: *achievement,
+        unlocked_at: now,
+      });
+    }
+  })
+  .await;
+  let message = create_unlock_message(&user, &unlocked);
+  let result = match channel_id {
+    Some(channel_id) => channel_id.send_message(&ctx.http, message).await,
+    None => match user.userId.parse::<u64>() {
+      Ok(id) => UserId::new(id).direct_message(&ctx.http, message).await,
+      Err(_) => return,
+    },
+  };
+  if let Err(why) = result {
+    error!("Can't announce the achievement: {:?}", why);
+  }
+}
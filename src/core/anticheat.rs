@@ -0,0 +1,195 @@
+use serenity::builder::{CreateEmbed, CreateMessage};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use tracing::{error, info};
+
+use crate::commands::handle::{Problem, Submission};
+
+use crate::core::data::User;
+use crate::core::data::*;
+use crate::core::scoring::is_during_match;
+use crate::utils::message_creator::*;
+
+// a solve faster than this many seconds per rating point of the problem is suspicious
+const FAST_SOLVE_SECONDS_PER_RATING: f64 = 0.15;
+const DEFAULT_PROBLEM_RATING: i32 = 800;
+// a solve this soon after another player of the match solved the same problem is suspicious
+const SHARED_SOLVE_SECONDS: u64 = 5 * 60;
+
+// an accepted submission of a match that looks off, for the moderators to have a look at
+#[derive(Clone, Debug, PartialEq)]
+pub struct SuspiciousSolve {
+  pub user_id: String,
+  pub handle: String,
+  pub problem: Problem,
+  pub submission_id: u64,
+  pub contest_id: Option<u32>,
+  pub reasons: Vec<String>,
+}
+
+// the fastest a problem of `rating` can honestly be solved after it appeared
+pub fn get_min_solve_seconds(rating: i32) -> u64 {
+  (rating.max(DEFAULT_PROBLEM_RATING) as f64 * FAST_SOLVE_SECONDS_PER_RATING) as u64
+}
+
+/*
+  The reasons the accepted `submission` of `handle` on a problem shown at `begin` looks off: it
+  came too fast for the rating of the problem, it is the first time the handle uses the language,
+  it was sent from a team or it came right after another player of the match, a teammate or an
+  opponent, solved the same problem. `others` are their handles and unix solve times
+*/
+pub fn get_suspicion_reasons(
+  handle: &str,
+  submission: &Submission,
+  history: &[Submission],
+  begin: u64,
+  others: &[(String, u64)],
+) -> Vec<String> {
+  let mut reasons = Vec::new();
+  let rating = submission
+    .problem
+    .difficulty()
+    .unwrap_or(DEFAULT_PROBLEM_RATING);
+  let taken = submission.creationTimeSeconds.saturating_sub(begin);
+  if taken < get_min_solve_seconds(rating) {
+    reasons.push(format!(
+      "Solved a {rating} rated problem {taken} second(s) after it was shown"
+    ));
+  }
+  let used_language_before = history.iter().any(|previous| {
+    previous.creationTimeSeconds < submission.creationTimeSeconds
+      && previous.programmingLanguage == submission.programmingLanguage
+  });
+  if !used_language_before {
+    reasons.push(format!(
+      "First submission of `{handle}` in {}",
+      submission.programmingLanguage
+    ));
+  }
+  let members = submission
+    .author
+    .members
+    .iter()
+    .filter(|member| !member.handle.eq_ignore_ascii_case(handle))
+    .map(|member| format!("`{}`", member.handle))
+    .collect::<Vec<_>>();
+  if let Some(team) = &submission.author.teamName {
+    reasons.push(format!(
+      "Submitted from the team `{team}` with {}",
+      members.join(", ")
+    ));
+  }
+  for (other, solved_at) in others.iter() {
+    let after = submission.creationTimeSeconds.checked_sub(*solved_at);
+    if let Some(after) = after.filter(|after| *after < SHARED_SOLVE_SECONDS) {
+      reasons.push(format!(
+        "Solved {after} second(s) after `{other}` solved the same problem"
+      ));
+    }
+  }
+  reasons
+}
+
+/*
+  Check the first accepted submission of the player on `problem` during the match, `others` are the
+  other players who solved it and when
+*/
+pub fn check_solve(
+  user: &User,
+  problem: &Problem,
+  submissions: &[Submission],
+  begin: u64,
+  end: u64,
+  others: &[(String, u64)],
+) -> Option<SuspiciousSolve> {
+  let submission = submissions
+    .iter()
+    .filter(|submission| {
+      submission.problem == *problem
+        && submission.verdict.as_deref() == Some("OK")
+        && is_during_match(submission.creationTimeSeconds, begin, end)
+    })
+    .min_by_key(|submission| submission.creationTimeSeconds)?;
+  let reasons = get_suspicion_reasons(&user.handle, submission, submissions, begin, others);
+  if reasons.is_empty() {
+    return None;
+  }
+  Some(SuspiciousSolve {
+    user_id: user.userId.clone(),
+    handle: user.handle.clone(),
+    problem: problem.clone(),
+    submission_id: submission.id,
+    contest_id: submission.contestId,
+    reasons,
+  })
+}
+
+fn submission_url(solve: &SuspiciousSolve) -> String {
+  let contest_id = solve.contest_id.unwrap_or_default();
+  let section = if contest_id >= 100000 {
+    "gym"
+  } else {
+    "contest"
+  };
+  format!(
+    "https://codeforces.com/{section}/{contest_id}/submission/{id}",
+    id = solve.submission_id
+  )
+}
+
+fn create_report_message(match_message: &Message, solve: &SuspiciousSolve) -> CreateMessage {
+  let embed = CreateEmbed::new()
+    .title("Suspicious solve")
+    .field(
+      "Player",
+      format!("<@{}> ({})", solve.user_id, solve.handle),
+      false,
+    )
+    .field(
+      "Problem",
+      create_href(problem_url(&solve.problem), &solve.problem.name),
+      true,
+    )
+    .field(
+      "Submission",
+      create_href(submission_url(solve), &solve.submission_id.to_string()),
+      true,
+    )
+    .field("Match", match_message.link(), false)
+    .field("Why", solve.reasons.join("\n"), false)
+    .colour(Colour::ORANGE)
+    .timestamp(Timestamp::now());
+  CreateMessage::new().embed(embed)
+}
+
+/*
+  Send the suspicious solves of a match to the moderator channel of its guild, never to the match
+  channel. Nothing is sent when the guild has no moderator channel
+*/
+pub async fn report_suspicious_solves(
+  ctx: &Context,
+  match_message: &Message,
+  solves: Vec<SuspiciousSolve>,
+) {
+  if solves.is_empty() {
+    return;
+  }
+  let config = get_guild_config(ctx, match_message.guild_id).await;
+  let channel_id = match config.moderator_channel {
+    Some(channel_id) => channel_id,
+    None => {
+      info!(
+        "{} suspicious solve(s) not reported, no moderator channel",
+        solves.len()
+      );
+      return;
+    }
+  };
+  for solve in solves.iter() {
+    let message = create_report_message(match_message, solve);
+    if let Err(why) = channel_id.send_message(&ctx.http, message).await {
+      error!("Can't report a suspicious solve: {why}");
+    }
+  }
+}
@@ -1,5 +1,5 @@
 use serde_json::Result as SerdeResult;
-use serenity::all::Message;
+use serenity::all::{ChannelId, GuildId, Message, MessageId, UserId};
 use std::time::SystemTime;
 
 use serenity::gateway::ShardManager;
@@ -11,6 +11,11 @@ use tokio::io::{self, AsyncReadExt, AsyncWriteExt, BufWriter};
 use tokio::time::Duration;
 
 use crate::commands::handle::*;
+use crate::core::achievement::UnlockedAchievement;
+use crate::core::pool::ProblemPool;
+use crate::core::scoring::{ProblemAttempt, ScoringStyle};
+use crate::core::tournament::Tournament;
+use crate::utils::time::unix_seconds;
 
 use reqwest;
 
@@ -20,6 +25,9 @@ use tracing::{error, info, warn};
 
 use serde::{Deserialize, Serialize};
 
+// how long a duel lasts when it doesn't say
+pub const DUEL_DURATION: Duration = Duration::from_secs(60 * 90);
+
 #[allow(non_snake_case)]
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 pub struct User {
@@ -29,12 +37,23 @@ pub struct User {
   pub active_challenge: Option<Problem>,
   pub last_time_since_challenge: Option<SystemTime>,
   pub duel_id: Option<usize>,
+  // guild whose timezone is used for streak reminders, `None` when they are off
+  #[serde(default)]
+  pub streak_reminder: Option<String>,
+  #[serde(default)]
+  pub last_streak_reminder: Option<i64>,
+  #[serde(default)]
+  pub duels_won: u32,
+  #[serde(default)]
+  pub achievements: Vec<UnlockedAchievement>,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 pub enum DuelType {
   DUEL,
   LOCKOUT,
+  VIRTUAL,
+  MASHUP,
 }
 
 #[allow(non_snake_case)]
@@ -49,9 +68,90 @@ pub struct Duel {
   pub score_distribution: Option<Vec<u32>>,
   pub match_duration: Option<Duration>,
   pub problems_point: Option<Vec<u32>>,
+  // the codeforces contest a virtual participation replays
+  #[serde(default)]
+  pub contest: Option<Contest>,
+  #[serde(default)]
+  pub scoring: Option<ScoringStyle>,
+  // the team of every player, `None` when everyone plays alone
+  #[serde(default)]
+  pub teams: Option<Vec<usize>>,
+  // the tournament the duel decides a pairing of
+  #[serde(default)]
+  pub tournament_id: Option<usize>,
+  // what every player did on every problem, kept up to date when the match is scored by style
+  #[serde(default)]
+  pub attempts: Option<Vec<Vec<ProblemAttempt>>>,
+  // points lost for every rejected submission before a claim, when the points decay
+  #[serde(default)]
+  pub wrong_penalty: Option<u32>,
+  // the points every problem was claimed for, 0 while it is open
+  #[serde(default)]
+  pub claimed_points: Option<Vec<u32>>,
+  // when every problem was claimed, in seconds after the start
+  #[serde(default)]
+  pub claimed_at: Option<Vec<u64>>,
+  // the user ids of the players who gave up a duel, they stay in it to be ranked last
+  #[serde(default)]
+  pub gave_up: Vec<String>,
+  // when the match ends once pauses and extensions are counted, `None` until it changed
+  #[serde(default)]
+  pub end_time: Option<SystemTime>,
+  // when the clock was frozen, `None` while it runs
+  #[serde(default)]
+  pub paused_at: Option<SystemTime>,
+  // the unix times every finished pause started and ended at
+  #[serde(default)]
+  pub pauses: Vec<(u64, u64)>,
 }
 
 impl Duel {
+  pub fn get_end_time(&self) -> SystemTime {
+    self
+      .end_time
+      .unwrap_or(self.begin_time + self.match_duration.unwrap_or(DUEL_DURATION))
+  }
+  // the time left stays the same while the match is paused
+  pub fn get_time_left(&self) -> Duration {
+    let now = self.paused_at.unwrap_or_else(SystemTime::now);
+    self.get_end_time().duration_since(now).unwrap_or_default()
+  }
+  pub fn is_paused(&self) -> bool {
+    self.paused_at.is_some()
+  }
+  pub fn pause(&mut self) {
+    if self.paused_at.is_none() {
+      self.paused_at = Some(SystemTime::now());
+    }
+  }
+  // push the end back by how long the match was paused
+  pub fn resume(&mut self) {
+    let end_time = self.get_end_time();
+    if let Some(paused_at) = self.paused_at.take() {
+      self.end_time = Some(end_time + paused_at.elapsed().unwrap_or_default());
+      self
+        .pauses
+        .push((unix_seconds(paused_at), unix_seconds(SystemTime::now())));
+    }
+  }
+  // how many of the first `seconds` after the start the clock was running
+  pub fn get_running_seconds(&self, seconds: u64) -> u64 {
+    let at = unix_seconds(self.begin_time) + seconds;
+    let current = self
+      .paused_at
+      .map(|paused_at| (unix_seconds(paused_at), u64::MAX));
+    let paused = self
+      .pauses
+      .iter()
+      .copied()
+      .chain(current)
+      .map(|(from, to)| to.min(at).saturating_sub(from))
+      .sum::<u64>();
+    seconds.saturating_sub(paused)
+  }
+  pub fn extend(&mut self, extra: Duration) {
+    self.end_time = Some(self.get_end_time() + extra);
+  }
   pub fn set_point(&mut self, index: usize) {
     if let Some(ref mut points) = self.problems_point {
       if let Some(element) = points.get_mut(index) {
@@ -59,6 +159,21 @@ impl Duel {
       }
     }
   }
+  pub fn set_claim(&mut self, index: usize, points: u32, solved_at: u64) {
+    let problem_count = self.problems.len();
+    let claimed_points = self
+      .claimed_points
+      .get_or_insert_with(|| vec![0; problem_count]);
+    if let Some(element) = claimed_points.get_mut(index) {
+      *element = points;
+    }
+    let claimed_at = self
+      .claimed_at
+      .get_or_insert_with(|| vec![0; problem_count]);
+    if let Some(element) = claimed_at.get_mut(index) {
+      *element = solved_at;
+    }
+  }
   pub fn add_score(&mut self, index: usize, del: u32) {
     if let Some(ref mut scores) = self.score_distribution {
       if let Some(score) = scores.get_mut(index) {
@@ -75,6 +190,14 @@ impl Duel {
     if let Some(ref mut scores) = self.score_distribution {
       scores.remove(index.unwrap());
     }
+    if let Some(ref mut teams) = self.teams {
+      teams.remove(index.unwrap());
+    }
+    if let Some(ref mut attempts) = self.attempts {
+      if index.unwrap() < attempts.len() {
+        attempts.remove(index.unwrap());
+      }
+    }
   }
 }
 
@@ -87,10 +210,130 @@ impl PartialEq for Duel {
   }
 }
 
+// settings a guild changes with `~config`
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct GuildConfig {
+  pub guild_id: String,
+  #[serde(default)]
+  pub pool: Option<ProblemPool>,
+  #[serde(default)]
+  pub potd: Option<PotdConfig>,
+  // minutes away from UTC
+  #[serde(default)]
+  pub utc_offset: i32,
+  // where upcoming codeforces rounds are announced
+  #[serde(default)]
+  pub contest_channel: Option<ChannelId>,
+  // where the rating changes of the members are posted after a round
+  #[serde(default)]
+  pub rating_channel: Option<ChannelId>,
+  // where suspicious solves of the matches are reported
+  #[serde(default)]
+  pub moderator_channel: Option<ChannelId>,
+  // how many problems can be replaced before a match starts, `None` is the default
+  #[serde(default)]
+  pub max_rerolls: Option<u32>,
+  // spectators only see the problems of a match once it ended
+  #[serde(default)]
+  pub hide_spectator_problems: bool,
+}
+
+// where and when the problem of the day is posted, `time` is in minutes after midnight
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct PotdConfig {
+  pub channel_id: ChannelId,
+  pub time: u32,
+  pub min_rating: u32,
+  pub max_rating: u32,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct ProblemOfTheDay {
+  pub guild_id: String,
+  pub channel_id: ChannelId,
+  pub day: i64,
+  pub problem: Problem,
+  pub posted_at: u64,
+  pub tallied: bool,
+}
+
+// a reminder posted for an upcoming contest, `lead` is how many seconds before the start
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct ContestReminder {
+  pub guild_id: String,
+  pub contest_id: u32,
+  pub start_time: u64,
+  pub lead: u64,
+  pub channel_id: ChannelId,
+  pub message_id: MessageId,
+}
+
+// a live standings message of a running round, `solved` holds the (party, problem index) pairs
+// that were already announced
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct ContestTracker {
+  pub contest_id: u32,
+  pub message: Message,
+  pub solved: Vec<(String, String)>,
+  // only the members of this guild are shown
+  #[serde(default)]
+  pub guild_id: Option<GuildId>,
+}
+
+// a member waiting in the duel queue, `band` is the widest rating gap they accept
 #[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct QueueEntry {
+  pub user_id: String,
+  pub rating: u32,
+  pub band: Option<u32>,
+  pub joined_at: u64,
+  pub message: Message,
+}
+
+// how a player did in a finished match
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct PlayerResult {
+  pub user_id: String,
+  pub handle: String,
+  pub rank: usize,
+  // indices of the problems credited to the player
+  pub solved: Vec<String>,
+  pub wrong_attempts: u32,
+  pub penalty: u64,
+}
+
+// a finished match, kept so results and stats outlive it
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct MatchRecord {
+  pub duel_type: DuelType,
+  pub channel_id: ChannelId,
+  pub begin_time: SystemTime,
+  pub end_time: SystemTime,
+  pub problems: Vec<Problem>,
+  pub results: Vec<PlayerResult>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
 pub struct Data {
   pub data: Vec<User>,
   pub duels: Vec<Duel>,
+  #[serde(default)]
+  pub guilds: Vec<GuildConfig>,
+  #[serde(default)]
+  pub potds: Vec<ProblemOfTheDay>,
+  #[serde(default)]
+  pub contest_reminders: Vec<ContestReminder>,
+  // contests whose rating changes were already announced
+  #[serde(default)]
+  pub rated_contests: Vec<u32>,
+  #[serde(default)]
+  pub trackers: Vec<ContestTracker>,
+  #[serde(default)]
+  pub match_history: Vec<MatchRecord>,
+  #[serde(default)]
+  pub tournaments: Vec<Tournament>,
+  #[serde(default)]
+  pub queue: Vec<QueueEntry>,
 }
 
 pub struct UserData;
@@ -104,12 +347,12 @@ impl TypeMapKey for UserData {
 pub struct Contest {
   pub id: u32,
   pub name: String,
-  r#type: String,
-  phase: String,
+  pub r#type: String,
+  pub phase: String,
   frozen: bool,
-  durationSeconds: u64,
-  startTimeSeconds: Option<u64>,
-  relativeTimeSeconds: Option<i64>,
+  pub durationSeconds: u64,
+  pub startTimeSeconds: Option<u64>,
+  pub relativeTimeSeconds: Option<i64>,
   preparedBy: Option<String>,
   websiteUrl: Option<String>,
   description: Option<String>,
@@ -217,7 +460,7 @@ pub async fn add_user_to_data(ctx: &Context, user_id: &String, handle: &String)
       None => {
         let user_data = Data {
           data: Vec::from([test_data]),
-          duels: Vec::new(),
+          ..Default::default()
         };
         let mut data = ctx.data.write().await;
         data.insert::<UserData>(Arc::new(RwLock::new(user_data)));
@@ -228,24 +471,47 @@ pub async fn add_user_to_data(ctx: &Context, user_id: &String, handle: &String)
   Ok(())
 }
 
-fn generate_duel_id(duels: &Vec<Duel>) -> usize {
-  if duels.len() == 0 {
-    return 0;
-  }
-  duels
-    .clone()
-    .sort_by(|duel_a, duel_b| duel_a.duel_id.partial_cmp(&duel_b.duel_id).unwrap());
+// the smallest id no running duel uses
+fn generate_duel_id(duels: &[Duel]) -> usize {
   let mut duel_id = 0;
-  for duel in duels.iter() {
-    if duel.duel_id == duel_id {
-      duel_id += 1;
-    } else {
-      break;
-    }
+  while duels.iter().any(|duel| duel.duel_id == duel_id) {
+    duel_id += 1;
   }
   duel_id
 }
 
+// give `duel` a fresh id, put its players in it and store it
+fn push_duel(user_data: &mut Data, mut duel: Duel) -> Duel {
+  let duel_id = generate_duel_id(&user_data.duels);
+  duel.duel_id = duel_id;
+  for player in duel.players.iter_mut() {
+    player.duel_id = Some(duel_id);
+    if let Some(user) = user_data
+      .data
+      .iter_mut()
+      .find(|user| user.userId == player.userId)
+    {
+      user.duel_id = Some(duel_id);
+    }
+  }
+  user_data.duels.push(duel.clone());
+  duel
+}
+
+// store a new match and return it with its id
+pub async fn create_match(ctx: &Context, duel: Duel) -> Duel {
+  let duel = {
+    let data_read = ctx.data.read().await;
+    let user_data_lock = data_read
+      .get::<UserData>()
+      .expect("Expect UserData in Type Map");
+    let mut user_data = user_data_lock.write().await;
+    push_duel(&mut user_data, duel)
+  };
+  let _ = update_json(ctx).await;
+  duel
+}
+
 pub async fn get_duels(ctx: &Context) -> Option<Vec<Duel>> {
   let data = get_data(&ctx).await.unwrap();
   Some(data.duels.clone())
@@ -276,22 +542,14 @@ pub async fn edit_duel(
       .expect("Expect UserData in Type Map");
     let mut user_data = user_data_lock.write().await;
     if problems == None {
-      let mut duel_id_to_be_removed: Vec<usize> = Vec::new();
-      let mut new_user_data_duels: Vec<Duel> = Vec::new();
-      for duel in user_data.duels.iter() {
-        for user_to_delete in users.iter() {
-          if user_to_delete.duel_id == None {
-            continue;
-          }
-          if user_to_delete.duel_id.unwrap() == duel.duel_id {
-            duel_id_to_be_removed.push(duel.duel_id);
-          } else {
-            new_user_data_duels.push(duel.clone());
-          }
-        }
-      }
-
-      user_data.duels = new_user_data_duels;
+      // the duels any of the users is in
+      let duel_id_to_be_removed = users
+        .iter()
+        .filter_map(|user| user.duel_id)
+        .collect::<Vec<_>>();
+      user_data
+        .duels
+        .retain(|duel| !duel_id_to_be_removed.contains(&duel.duel_id));
 
       user_data.data.iter_mut().for_each(|user| {
         if user.duel_id != None && duel_id_to_be_removed.contains(&user.duel_id.unwrap()) {
@@ -299,53 +557,80 @@ pub async fn edit_duel(
         }
       });
     } else {
-      let duels = user_data.duels.clone();
-      let new_duel_id = generate_duel_id(&duels.clone());
-      let mut users_to_duel = users.clone();
       let current = SystemTime::now();
-      for user_to_add in users_to_duel.iter_mut() {
-        user_data.data.iter_mut().for_each(|user| {
-          if user == user_to_add {
-            user.duel_id = Some(new_duel_id);
-            user_to_add.duel_id = Some(new_duel_id);
-          }
-        })
-      }
       let number_of_problems = problems.clone().unwrap().len();
-      user_data.duels.push(Duel {
-        duel_id: new_duel_id,
-        players: users_to_duel,
-        begin_time: current,
-        problems: problems.unwrap(),
-        channel_id: msg.unwrap().clone(),
-        duel_type: if number_of_problems == 1 {
-          DuelType::DUEL
-        } else {
-          DuelType::LOCKOUT
-        },
-        score_distribution: if number_of_problems == 1 {
-          None
-        } else {
-          Some(vec![0; number_of_problems])
+      push_duel(
+        &mut user_data,
+        Duel {
+          duel_id: 0,
+          players: users.clone(),
+          begin_time: current,
+          problems: problems.unwrap(),
+          channel_id: msg.unwrap().clone(),
+          duel_type: if number_of_problems == 1 {
+            DuelType::DUEL
+          } else {
+            DuelType::LOCKOUT
+          },
+          score_distribution: if number_of_problems == 1 {
+            None
+          } else {
+            Some(vec![0; number_of_problems])
+          },
+          match_duration: duration,
+          problems_point: problems_score,
+          contest: None,
+          scoring: None,
+          teams: None,
+          tournament_id: None,
+          attempts: None,
+          wrong_penalty: None,
+          claimed_points: None,
+          claimed_at: None,
+          gave_up: Vec::new(),
+          end_time: None,
+          paused_at: None,
+          pauses: Vec::new(),
         },
-        match_duration: duration,
-        problems_point: problems_score,
-      })
+      );
     }
   }
   let _ = update_json(&ctx).await;
 }
 
-pub async fn create_duel(ctx: &Context, msg: &Message, users: Vec<User>, problem: &Problem) {
-  edit_duel(
-    &ctx,
-    Some(&msg),
-    &users,
-    Some(Vec::from([problem.clone()])),
-    None,
-    None,
+pub async fn create_duel(
+  ctx: &Context,
+  msg: &Message,
+  users: Vec<User>,
+  problem: &Problem,
+) -> Duel {
+  create_match(
+    ctx,
+    Duel {
+      duel_id: 0,
+      players: users,
+      begin_time: SystemTime::now(),
+      problems: Vec::from([problem.clone()]),
+      channel_id: msg.clone(),
+      duel_type: DuelType::DUEL,
+      score_distribution: None,
+      match_duration: None,
+      problems_point: None,
+      contest: None,
+      scoring: None,
+      teams: None,
+      tournament_id: None,
+      attempts: None,
+      wrong_penalty: None,
+      claimed_points: None,
+      claimed_at: None,
+      gave_up: Vec::new(),
+      end_time: None,
+      paused_at: None,
+      pauses: Vec::new(),
+    },
   )
-  .await;
+  .await
 }
 
 pub async fn create_lockout(
@@ -355,15 +640,53 @@ pub async fn create_lockout(
   problems: &Vec<Problem>,
   duration: Duration,
   problems_point: Vec<u32>,
-) {
-  edit_duel(
-    &ctx,
-    Some(&msg),
-    &users,
-    Some(problems.clone()),
-    Some(duration),
-    Some(problems_point),
+  teams: Option<Vec<usize>>,
+  scoring: Option<ScoringStyle>,
+  wrong_penalty: Option<u32>,
+) -> Duel {
+  let number_of_players = users.len();
+  create_match(
+    ctx,
+    Duel {
+      duel_id: 0,
+      players: users,
+      begin_time: SystemTime::now(),
+      problems: problems.clone(),
+      channel_id: msg.clone(),
+      duel_type: DuelType::LOCKOUT,
+      score_distribution: Some(vec![0; number_of_players]),
+      match_duration: Some(duration),
+      problems_point: Some(problems_point),
+      contest: None,
+      scoring,
+      teams,
+      tournament_id: None,
+      attempts: None,
+      wrong_penalty,
+      claimed_points: None,
+      claimed_at: None,
+      gave_up: Vec::new(),
+      end_time: None,
+      paused_at: None,
+      pauses: Vec::new(),
+    },
   )
+  .await
+}
+
+// take a player out of a running match, the match is dropped once nobody is left
+pub async fn leave_duel(ctx: &Context, duel_id: usize, user_id: &String) {
+  edit_data(ctx, |data| {
+    if let Some(duel) = data.duels.iter_mut().find(|duel| duel.duel_id == duel_id) {
+      duel.remove_user(user_id.clone());
+    }
+    data
+      .duels
+      .retain(|duel| duel.duel_id != duel_id || !duel.players.is_empty());
+    if let Some(user) = data.data.iter_mut().find(|user| &user.userId == user_id) {
+      user.duel_id = None;
+    }
+  })
   .await;
 }
 
@@ -410,6 +733,25 @@ pub async fn remove_problem_from_user(ctx: &Context, user_id: &String) -> SerdeR
   return add_problem_to_user(&ctx, &user_id, None).await;
 }
 
+// apply `edit` to the registered user with `user_id` and save it
+pub async fn edit_user(ctx: &Context, user_id: &String, edit: impl FnOnce(&mut User)) {
+  {
+    let data_read = ctx.data.read().await;
+    let user_data_lock = data_read
+      .get::<UserData>()
+      .expect("Expect UserData in TypeMap");
+    let mut user_data = user_data_lock.write().await;
+    if let Some(user) = user_data
+      .data
+      .iter_mut()
+      .find(|user| &user.userId == user_id)
+    {
+      edit(user);
+    }
+  }
+  let _ = update_json(ctx).await;
+}
+
 pub async fn add_points_to_user(ctx: &Context, user_id: &String, points: u64) {
   {
     let data_read = ctx.data.read().await;
@@ -492,10 +834,7 @@ pub async fn initialize_data(client: &Client) -> io::Result<()> {
       warn!("Json error : {:?}", why);
       {
         let mut data = client.data.write().await;
-        data.insert::<UserData>(Arc::new(RwLock::new(Data {
-          data: Vec::new(),
-          duels: Vec::new(),
-        })));
+        data.insert::<UserData>(Arc::new(RwLock::new(Data::default())));
       }
       return Ok(());
     }
@@ -519,3 +858,158 @@ pub async fn get_data(ctx: &Context) -> Result<Data, String> {
   let data = data_lock.read().await;
   return Ok((*data).clone());
 }
+
+// the registered users who are members of `guild_id`
+pub async fn get_guild_users(ctx: &Context, guild_id: GuildId) -> Vec<User> {
+  let users = match get_data(ctx).await {
+    Ok(data) => data.data,
+    Err(_) => return Vec::new(),
+  };
+  keep_guild_members(ctx, Some(guild_id), users).await
+}
+
+// the `users` who are members of `guild_id`, all of them outside of a guild
+pub async fn keep_guild_members(
+  ctx: &Context,
+  guild_id: Option<GuildId>,
+  users: Vec<User>,
+) -> Vec<User> {
+  let guild_id = match guild_id {
+    Some(id) => id,
+    None => return users,
+  };
+  let mut members = Vec::new();
+  for user in users {
+    let user_id = match user.userId.parse::<u64>() {
+      Ok(id) => UserId::new(id),
+      Err(_) => continue,
+    };
+    if guild_id.member(ctx, user_id).await.is_ok() {
+      members.push(user);
+    }
+  }
+  members
+}
+
+pub async fn get_guild_config(ctx: &Context, guild_id: Option<GuildId>) -> GuildConfig {
+  let guild_id = match guild_id {
+    Some(id) => id.to_string(),
+    None => return GuildConfig::default(),
+  };
+  if let Ok(data) = get_data(ctx).await {
+    if let Some(config) = data
+      .guilds
+      .iter()
+      .find(|config| config.guild_id == guild_id)
+    {
+      return config.clone();
+    }
+  }
+  GuildConfig {
+    guild_id,
+    ..Default::default()
+  }
+}
+
+// apply `edit` to the config of `guild_id` (creating it if needed) and save it
+pub async fn edit_guild_config(
+  ctx: &Context,
+  guild_id: GuildId,
+  edit: impl FnOnce(&mut GuildConfig),
+) {
+  {
+    let data_read = ctx.data.read().await;
+    let user_data_lock = data_read
+      .get::<UserData>()
+      .expect("Expect UserData in TypeMap");
+    let mut user_data = user_data_lock.write().await;
+    let guild_id = guild_id.to_string();
+    let index = match user_data
+      .guilds
+      .iter()
+      .position(|config| config.guild_id == guild_id)
+    {
+      Some(index) => index,
+      None => {
+        user_data.guilds.push(GuildConfig {
+          guild_id,
+          ..Default::default()
+        });
+        user_data.guilds.len() - 1
+      }
+    };
+    edit(&mut user_data.guilds[index]);
+  }
+  let _ = update_json(ctx).await;
+}
+
+// the pool a request asked for, otherwise the one of the guild
+pub async fn get_problem_pool(
+  ctx: &Context,
+  guild_id: Option<GuildId>,
+  requested: Option<ProblemPool>,
+) -> ProblemPool {
+  match requested {
+    Some(pool) => pool,
+    None => get_guild_config(ctx, guild_id)
+      .await
+      .pool
+      .unwrap_or_default(),
+  }
+}
+
+pub async fn get_potd(ctx: &Context, guild_id: &String) -> Option<ProblemOfTheDay> {
+  let data = get_data(ctx).await.ok()?;
+  data
+    .potds
+    .iter()
+    .find(|potd| &potd.guild_id == guild_id)
+    .cloned()
+}
+
+// store the problem of the day of a guild, replacing the previous one
+pub async fn set_potd(ctx: &Context, potd: ProblemOfTheDay) {
+  {
+    let data_read = ctx.data.read().await;
+    let user_data_lock = data_read
+      .get::<UserData>()
+      .expect("Expect UserData in TypeMap");
+    let mut user_data = user_data_lock.write().await;
+    user_data
+      .potds
+      .retain(|current| current.guild_id != potd.guild_id);
+    user_data.potds.push(potd);
+  }
+  let _ = update_json(ctx).await;
+}
+
+// apply `edit` to the whole data and save it
+pub async fn edit_data(ctx: &Context, edit: impl FnOnce(&mut Data)) {
+  {
+    let data_read = ctx.data.read().await;
+    let user_data_lock = data_read
+      .get::<UserData>()
+      .expect("Expect UserData in TypeMap");
+    let mut user_data = user_data_lock.write().await;
+    edit(&mut user_data);
+  }
+  let _ = update_json(ctx).await;
+}
+
+// store the current state of a running match
+pub async fn save_duel(ctx: &Context, duel: &Duel) {
+  edit_data(ctx, |data| {
+    if let Some(saved) = data
+      .duels
+      .iter_mut()
+      .find(|saved| saved.duel_id == duel.duel_id)
+    {
+      *saved = duel.clone();
+    }
+  })
+  .await;
+}
+
+pub async fn archive_match(ctx: &Context, record: MatchRecord) {
+  edit_data(ctx, |data| data.match_history.push(record)).await;
+}
@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+
+use crate::commands::giveme::{MAX_RATING, MIN_RATING};
+use crate::commands::handle::{Problem, ProblemStatistic};
+use crate::core::data::Contest;
+use crate::core::pool::ContestCategory;
+
+// below this many rated problems a division falls back to the fit of the whole problemset
+const MIN_SAMPLES: usize = 30;
+
+// rating = intercept + slope * ln(solvedCount + 1)
+#[derive(Clone, Copy, Debug)]
+struct Fit {
+  intercept: f64,
+  slope: f64,
+}
+
+impl Fit {
+  fn from_samples(samples: &[(f64, f64)]) -> Option<Fit> {
+    if samples.len() < 2 {
+      return None;
+    }
+    let n = samples.len() as f64;
+    let mean_x = samples.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = samples.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let covariance = samples
+      .iter()
+      .map(|(x, y)| (x - mean_x) * (y - mean_y))
+      .sum::<f64>();
+    let variance = samples
+      .iter()
+      .map(|(x, _)| (x - mean_x) * (x - mean_x))
+      .sum::<f64>();
+    if variance == 0f64 {
+      return None;
+    }
+    let slope = covariance / variance;
+    Some(Fit {
+      intercept: mean_y - slope * mean_x,
+      slope,
+    })
+  }
+
+  fn estimate(&self, solved_count: i32) -> i32 {
+    let rating = self.intercept + self.slope * f64::ln(solved_count.max(0) as f64 + 1f64);
+    let rounded = (rating / 100f64).round() as i32 * 100;
+    rounded.clamp(MIN_RATING as i32, MAX_RATING as i32)
+  }
+}
+
+/*
+  Give every unrated problem an `estimated_rating`. The rated problems of each division are
+  used to fit how the rating drops with the number of people who solved a problem, the fit is
+  then applied to the solve count of the unrated problems of that division
+*/
+pub fn estimate_difficulties(
+  problems: &mut [Problem],
+  statistics: &[ProblemStatistic],
+  contests: &[Contest],
+) {
+  let solved_counts = statistics
+    .iter()
+    .map(|statistic| {
+      (
+        (statistic.contestId, statistic.index.as_str()),
+        statistic.solvedCount,
+      )
+    })
+    .collect::<HashMap<_, _>>();
+  let divisions = contests
+    .iter()
+    .map(|contest| (contest.id, ContestCategory::of(contest)[0]))
+    .collect::<HashMap<_, _>>();
+  let solved_count = |problem: &Problem| {
+    solved_counts
+      .get(&(problem.contestId, problem.index.as_str()))
+      .copied()
+  };
+  let division_of = |contest_id: Option<u32>| {
+    contest_id
+      .and_then(|id| divisions.get(&id).copied())
+      .unwrap_or(ContestCategory::Other)
+  };
+
+  let mut samples: Vec<(ContestCategory, f64, f64)> = Vec::new();
+  for problem in problems.iter() {
+    if let (Some(rating), Some(count)) = (problem.rating, solved_count(problem)) {
+      samples.push((
+        division_of(problem.contestId),
+        f64::ln(count.max(0) as f64 + 1f64),
+        rating as f64,
+      ));
+    }
+  }
+  let global_fit = Fit::from_samples(&samples.iter().map(|(_, x, y)| (*x, *y)).collect::<Vec<_>>());
+  let fits = ContestCategory::ALL
+    .iter()
+    .map(|category| {
+      let division_samples = samples
+        .iter()
+        .filter(|(division, _, _)| division == category)
+        .map(|(_, x, y)| (*x, *y))
+        .collect::<Vec<_>>();
+      let fit = if division_samples.len() >= MIN_SAMPLES {
+        Fit::from_samples(&division_samples).or(global_fit)
+      } else {
+        global_fit
+      };
+      (*category, fit)
+    })
+    .collect::<Vec<_>>();
+
+  for problem in problems.iter_mut() {
+    if problem.rating.is_some() {
+      continue;
+    }
+    let count = match solved_count(problem) {
+      Some(count) => count,
+      None => continue,
+    };
+    let division = division_of(problem.contestId);
+    if let Some((_, Some(fit))) = fits.iter().find(|(category, _)| *category == division) {
+      problem.estimated_rating = Some(fit.estimate(count));
+    }
+  }
+}
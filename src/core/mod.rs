@@ -1 +1,7 @@
-pub mod data;
\ No newline at end of file
+pub mod achievement;
+pub mod anticheat;
+pub mod data;
+pub mod estimator;
+pub mod pool;
+pub mod scoring;
+pub mod tournament;
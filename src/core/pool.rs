@@ -0,0 +1,211 @@
+use serde::{Deserialize, Serialize};
+
+use crate::core::data::Contest;
+use crate::utils::time::year_of;
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq)]
+pub enum ContestCategory {
+  Div1,
+  Div2,
+  Div3,
+  Div4,
+  Educational,
+  Global,
+  GoodBye,
+  KotlinHeroes,
+  Other,
+}
+
+impl ContestCategory {
+  pub const ALL: [ContestCategory; 9] = [
+    ContestCategory::Div1,
+    ContestCategory::Div2,
+    ContestCategory::Div3,
+    ContestCategory::Div4,
+    ContestCategory::Educational,
+    ContestCategory::Global,
+    ContestCategory::GoodBye,
+    ContestCategory::KotlinHeroes,
+    ContestCategory::Other,
+  ];
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      ContestCategory::Div1 => "div1",
+      ContestCategory::Div2 => "div2",
+      ContestCategory::Div3 => "div3",
+      ContestCategory::Div4 => "div4",
+      ContestCategory::Educational => "edu",
+      ContestCategory::Global => "global",
+      ContestCategory::GoodBye => "goodbye",
+      ContestCategory::KotlinHeroes => "kotlin",
+      ContestCategory::Other => "other",
+    }
+  }
+
+  fn parse(name: &str) -> Option<ContestCategory> {
+    match name {
+      "div1" | "d1" => Some(ContestCategory::Div1),
+      "div2" | "d2" => Some(ContestCategory::Div2),
+      "div3" | "d3" => Some(ContestCategory::Div3),
+      "div4" | "d4" => Some(ContestCategory::Div4),
+      "edu" | "educational" => Some(ContestCategory::Educational),
+      "global" => Some(ContestCategory::Global),
+      "goodbye" => Some(ContestCategory::GoodBye),
+      "kotlin" | "kotlinheroes" => Some(ContestCategory::KotlinHeroes),
+      "other" => Some(ContestCategory::Other),
+      _ => None,
+    }
+  }
+
+  // every category a contest belongs to, a combined round is both div1 and div2
+  pub fn of(contest: &Contest) -> Vec<ContestCategory> {
+    let name = format!(
+      "{} {}",
+      contest.name,
+      contest.kind.clone().unwrap_or_default()
+    );
+    if name.contains("Educational") {
+      return vec![ContestCategory::Educational];
+    }
+    if name.contains("Kotlin Heroes") {
+      return vec![ContestCategory::KotlinHeroes];
+    }
+    let mut categories = Vec::new();
+    if name.contains("Global Round") {
+      categories.push(ContestCategory::Global);
+    }
+    if name.contains("Good Bye") {
+      categories.push(ContestCategory::GoodBye);
+    }
+    for (division, category) in [
+      ("Div. 1", ContestCategory::Div1),
+      ("Div. 2", ContestCategory::Div2),
+      ("Div. 3", ContestCategory::Div3),
+      ("Div. 4", ContestCategory::Div4),
+    ] {
+      if name.contains(division) {
+        categories.push(category);
+      }
+    }
+    if categories.is_empty() {
+      categories.push(ContestCategory::Other);
+    }
+    categories
+  }
+}
+
+/*
+  The contests problems are drawn from. Guilds can store their own pool with `~config pool`
+  and every command accepting `pool=...` can override it for a single request
+*/
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct ProblemPool {
+  pub categories: Vec<ContestCategory>,
+  pub contest_ids: Option<(u32, u32)>,
+  pub min_year: Option<i64>,
+}
+
+impl Default for ProblemPool {
+  fn default() -> Self {
+    ProblemPool {
+      categories: vec![
+        ContestCategory::Div1,
+        ContestCategory::Div2,
+        ContestCategory::Div3,
+        ContestCategory::Div4,
+        ContestCategory::Educational,
+      ],
+      contest_ids: None,
+      min_year: None,
+    }
+  }
+}
+
+impl ProblemPool {
+  /*
+    Parse a pool from tokens separated by spaces or commas, for example
+    `div2,div3,edu,ids:1500-1900,year:2020`. `all` selects every category
+  */
+  pub fn parse(spec: &str) -> Result<ProblemPool, String> {
+    let mut pool = ProblemPool {
+      categories: Vec::new(),
+      contest_ids: None,
+      min_year: None,
+    };
+    for token in spec
+      .split(|c: char| c == ',' || c.is_whitespace())
+      .filter(|token| !token.is_empty())
+    {
+      let token = token.to_lowercase();
+      if token == "all" {
+        pool.categories = ContestCategory::ALL.to_vec();
+      } else if let Some(range) = token.strip_prefix("ids:") {
+        let (from, to) = range
+          .split_once('-')
+          .ok_or("Contest ids should look like `ids:1000-1500`".to_owned())?;
+        let (from, to) = match (from.parse::<u32>(), to.parse::<u32>()) {
+          (Ok(from), Ok(to)) if from <= to => (from, to),
+          _ => return Err(format!("`{range}` is not a valid contest id range")),
+        };
+        pool.contest_ids = Some((from, to));
+      } else if let Some(year) = token.strip_prefix("year:") {
+        pool.min_year = Some(
+          year
+            .parse::<i64>()
+            .map_err(|_| format!("`{year}` is not a valid year"))?,
+        );
+      } else if let Some(category) = ContestCategory::parse(&token) {
+        if !pool.categories.contains(&category) {
+          pool.categories.push(category);
+        }
+      } else {
+        return Err(format!(
+          "Unknown pool option `{token}`, use {}, `all`, `ids:<from>-<to>` or `year:<year>`",
+          ContestCategory::ALL
+            .iter()
+            .map(|category| format!("`{}`", category.name()))
+            .collect::<Vec<_>>()
+            .join(", ")
+        ));
+      }
+    }
+    if pool.categories.is_empty() {
+      pool.categories = ProblemPool::default().categories;
+    }
+    Ok(pool)
+  }
+
+  pub fn describe(&self) -> String {
+    let mut description = self
+      .categories
+      .iter()
+      .map(|category| category.name())
+      .collect::<Vec<_>>()
+      .join(", ");
+    if let Some((from, to)) = self.contest_ids {
+      description += format!(", contests {from} to {to}").as_str();
+    }
+    if let Some(year) = self.min_year {
+      description += format!(", since {year}").as_str();
+    }
+    description
+  }
+
+  pub fn contains(&self, contest: &Contest) -> bool {
+    if let Some((from, to)) = self.contest_ids {
+      if contest.id < from || contest.id > to {
+        return false;
+      }
+    }
+    if let Some(year) = self.min_year {
+      match contest.startTimeSeconds {
+        Some(start) if year_of(start) >= year => {}
+        _ => return false,
+      }
+    }
+    ContestCategory::of(contest)
+      .iter()
+      .any(|category| self.categories.contains(category))
+  }
+}
@@ -0,0 +1,334 @@
+use serde::{Deserialize, Serialize};
+
+use crate::commands::handle::{Problem, Submission};
+
+// minutes added for every rejected attempt on a problem that ends up solved
+pub const ICPC_PENALTY_MINUTES: u64 = 20;
+// points lost for every rejected attempt on a problem that ends up solved
+pub const CF_WRONG_PENALTY: f64 = 50f64;
+// a solved problem is never worth less than this share of its points
+pub const CF_MIN_SHARE: f64 = 0.3;
+// a codeforces round problem loses `points / CF_DECAY_MINUTES` points every minute
+pub const CF_DECAY_MINUTES: f64 = 250f64;
+// verdicts that don't count as an attempt
+const IGNORED_VERDICTS: [&str; 3] = ["COMPILATION_ERROR", "TESTING", "SKIPPED"];
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq)]
+pub enum ScoringStyle {
+  // solved count, then penalty minutes
+  Icpc,
+  // points that decay with time, like a codeforces round
+  Codeforces,
+}
+
+impl ScoringStyle {
+  pub fn parse(name: &str) -> Result<ScoringStyle, String> {
+    match name {
+      "icpc" => Ok(ScoringStyle::Icpc),
+      "cf" | "codeforces" => Ok(ScoringStyle::Codeforces),
+      _ => Err(format!("`{name}` is not a scoring, try `icpc` or `cf`")),
+    }
+  }
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      ScoringStyle::Icpc => "ICPC",
+      ScoringStyle::Codeforces => "Codeforces",
+    }
+  }
+}
+
+// how a player did on one problem, `solved_at` is in seconds after the start
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct ProblemAttempt {
+  pub solved_at: Option<u64>,
+  pub wrong_attempts: u32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Score {
+  pub solved: u32,
+  pub penalty: u64,
+  pub points: f64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScoreboardRow {
+  // index of the player in the match
+  pub player: usize,
+  pub rank: usize,
+  pub score: Score,
+  pub attempts: Vec<ProblemAttempt>,
+}
+
+// whether something at `time` happened while a match running in [begin, end) was on
+pub fn is_during_match(time: u64, begin: u64, end: u64) -> bool {
+  begin <= time && time < end
+}
+
+// the time of the first accepted submission on `problem` made during the match
+pub fn get_first_accepted(
+  problem: &Problem,
+  submissions: &[Submission],
+  begin: u64,
+  end: u64,
+) -> Option<u64> {
+  submissions
+    .iter()
+    .filter(|submission| {
+      submission.problem == *problem
+        && submission.verdict.as_deref() == Some("OK")
+        && is_during_match(submission.creationTimeSeconds, begin, end)
+    })
+    .map(|submission| submission.creationTimeSeconds)
+    .min()
+}
+
+/*
+  The attempts on `problem` submitted in [begin, end). Rejections after the first accepted
+  submission and compilation errors don't count
+*/
+pub fn get_attempt(
+  problem: &Problem,
+  submissions: &[Submission],
+  begin: u64,
+  end: u64,
+) -> ProblemAttempt {
+  let mut relevant = submissions
+    .iter()
+    .filter(|submission| {
+      submission.problem == *problem && is_during_match(submission.creationTimeSeconds, begin, end)
+    })
+    .filter_map(|submission| {
+      let verdict = submission.verdict.as_deref()?;
+      if IGNORED_VERDICTS.contains(&verdict) {
+        return None;
+      }
+      Some((submission.creationTimeSeconds, verdict == "OK"))
+    })
+    .collect::<Vec<_>>();
+  relevant.sort_by_key(|(time, _)| *time);
+
+  let mut attempt = ProblemAttempt::default();
+  for (time, accepted) in relevant {
+    if accepted {
+      attempt.solved_at = Some(time - begin);
+      break;
+    }
+    attempt.wrong_attempts += 1;
+  }
+  attempt
+}
+
+pub fn icpc_penalty(attempt: &ProblemAttempt) -> Option<u64> {
+  let solved_at = attempt.solved_at?;
+  Some(solved_at / 60 + ICPC_PENALTY_MINUTES * attempt.wrong_attempts as u64)
+}
+
+pub fn cf_points(max_points: f64, attempt: &ProblemAttempt) -> Option<f64> {
+  decayed_points(max_points, attempt, CF_WRONG_PENALTY)
+}
+
+// what a problem is worth when solved at `attempt.solved_at`, losing `wrong_penalty` per rejection
+pub fn decayed_points(
+  max_points: f64,
+  attempt: &ProblemAttempt,
+  wrong_penalty: f64,
+) -> Option<f64> {
+  let minutes = (attempt.solved_at? / 60) as f64;
+  let points = max_points
+    - max_points / CF_DECAY_MINUTES * minutes
+    - wrong_penalty * attempt.wrong_attempts as f64;
+  Some(points.max(max_points * CF_MIN_SHARE).floor())
+}
+
+pub fn get_score(attempts: &[ProblemAttempt], max_points: &[f64]) -> Score {
+  let mut score = Score::default();
+  for (attempt, max_points) in attempts.iter().zip(max_points.iter()) {
+    if let (Some(penalty), Some(points)) = (icpc_penalty(attempt), cf_points(*max_points, attempt))
+    {
+      score.solved += 1;
+      score.penalty += penalty;
+      score.points += points;
+    }
+  }
+  score
+}
+
+fn is_tied(style: ScoringStyle, a: &Score, b: &Score) -> bool {
+  match style {
+    ScoringStyle::Icpc => a.solved == b.solved && a.penalty == b.penalty,
+    ScoringStyle::Codeforces => a.points == b.points,
+  }
+}
+
+// the attempts of every player on every problem during the match
+pub fn get_attempts(
+  problems: &[Problem],
+  user_submissions: &[Vec<Submission>],
+  begin: u64,
+  end: u64,
+) -> Vec<Vec<ProblemAttempt>> {
+  user_submissions
+    .iter()
+    .map(|submissions| {
+      problems
+        .iter()
+        .map(|problem| get_attempt(problem, submissions, begin, end))
+        .collect()
+    })
+    .collect()
+}
+
+// score the attempts of every player and sort them, tied players share a rank
+pub fn rank_attempts(
+  style: ScoringStyle,
+  attempts: &[Vec<ProblemAttempt>],
+  max_points: &[f64],
+) -> Vec<ScoreboardRow> {
+  let mut rows = attempts
+    .iter()
+    .enumerate()
+    .map(|(player, attempts)| ScoreboardRow {
+      player,
+      rank: 0,
+      score: get_score(attempts, max_points),
+      attempts: attempts.clone(),
+    })
+    .collect::<Vec<_>>();
+  rows.sort_by(|a, b| match style {
+    ScoringStyle::Icpc => b
+      .score
+      .solved
+      .cmp(&a.score.solved)
+      .then(a.score.penalty.cmp(&b.score.penalty)),
+    ScoringStyle::Codeforces => b.score.points.total_cmp(&a.score.points),
+  });
+  for pos in 0..rows.len() {
+    rows[pos].rank = if pos > 0 && is_tied(style, &rows[pos - 1].score, &rows[pos].score) {
+      rows[pos - 1].rank
+    } else {
+      pos + 1
+    };
+  }
+  rows
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  const BEGIN: u64 = 1_700_000_000;
+  const END: u64 = BEGIN + 90 * 60;
+
+  fn problem(index: &str) -> Problem {
+    serde_json::from_value(json!({
+      "contestId": 1950,
+      "index": index,
+      "name": "Problem",
+      "type": "PROGRAMMING",
+      "rating": 1200,
+      "tags": []
+    }))
+    .unwrap()
+  }
+
+  fn submission(index: &str, time: u64, verdict: &str) -> Submission {
+    serde_json::from_value(json!({
+      "id": time,
+      "contestId": 1950,
+      "creationTimeSeconds": time,
+      "relativeTimeSeconds": 2147483647u64,
+      "problem": problem(index),
+      "author": {
+        "contestId": 1950,
+        "members": [{ "handle": "tourist" }],
+        "participantType": "PRACTICE",
+        "ghost": false
+      },
+      "programmingLanguage": "C++17 (GCC 7-32)",
+      "verdict": verdict,
+      "testset": "TESTS",
+      "passedTestCount": 10,
+      "timeConsumedMillis": 15,
+      "memoryConsumedBytes": 0
+    }))
+    .unwrap()
+  }
+
+  #[test]
+  fn solve_before_the_start_does_not_count() {
+    let submissions = [submission("A", BEGIN - 1, "OK")];
+    assert_eq!(
+      get_first_accepted(&problem("A"), &submissions, BEGIN, END),
+      None
+    );
+  }
+
+  #[test]
+  fn solve_at_the_start_counts() {
+    let submissions = [submission("A", BEGIN, "OK")];
+    assert_eq!(
+      get_first_accepted(&problem("A"), &submissions, BEGIN, END),
+      Some(BEGIN)
+    );
+  }
+
+  #[test]
+  fn solve_in_the_last_second_counts() {
+    let submissions = [submission("A", END - 1, "OK")];
+    assert_eq!(
+      get_first_accepted(&problem("A"), &submissions, BEGIN, END),
+      Some(END - 1)
+    );
+  }
+
+  #[test]
+  fn solve_at_the_end_does_not_count() {
+    let submissions = [submission("A", END, "OK")];
+    assert_eq!(
+      get_first_accepted(&problem("A"), &submissions, BEGIN, END),
+      None
+    );
+  }
+
+  #[test]
+  fn earliest_solve_during_the_match_is_taken() {
+    let submissions = [
+      submission("A", BEGIN - 600, "OK"),
+      submission("A", BEGIN + 900, "OK"),
+      submission("A", BEGIN + 300, "OK"),
+    ];
+    assert_eq!(
+      get_first_accepted(&problem("A"), &submissions, BEGIN, END),
+      Some(BEGIN + 300)
+    );
+  }
+
+  #[test]
+  fn rejections_and_other_problems_are_not_solves() {
+    let submissions = [
+      submission("A", BEGIN + 60, "WRONG_ANSWER"),
+      submission("B", BEGIN + 120, "OK"),
+    ];
+    assert_eq!(
+      get_first_accepted(&problem("A"), &submissions, BEGIN, END),
+      None
+    );
+  }
+
+  #[test]
+  fn attempt_ignores_submissions_outside_the_match() {
+    let submissions = [
+      submission("A", BEGIN - 60, "WRONG_ANSWER"),
+      submission("A", BEGIN + 60, "WRONG_ANSWER"),
+      submission("A", BEGIN + 120, "COMPILATION_ERROR"),
+      submission("A", BEGIN + 600, "OK"),
+      submission("A", END, "WRONG_ANSWER"),
+    ];
+    let attempt = get_attempt(&problem("A"), &submissions, BEGIN, END);
+    assert_eq!(attempt.solved_at, Some(600));
+    assert_eq!(attempt.wrong_attempts, 1);
+  }
+}
@@ -0,0 +1,481 @@
+use serde::{Deserialize, Serialize};
+use serenity::model::prelude::*;
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq)]
+pub enum TournamentFormat {
+  SingleElimination,
+  DoubleElimination,
+  Swiss,
+}
+
+impl TournamentFormat {
+  pub fn parse(name: &str) -> Option<TournamentFormat> {
+    match name {
+      "single" | "se" => Some(TournamentFormat::SingleElimination),
+      "double" | "de" => Some(TournamentFormat::DoubleElimination),
+      "swiss" => Some(TournamentFormat::Swiss),
+      _ => None,
+    }
+  }
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      TournamentFormat::SingleElimination => "Single elimination",
+      TournamentFormat::DoubleElimination => "Double elimination",
+      TournamentFormat::Swiss => "Swiss",
+    }
+  }
+
+  // how many losses knock a player out, swiss never does
+  fn max_losses(&self) -> Option<u32> {
+    match self {
+      TournamentFormat::SingleElimination => Some(1),
+      TournamentFormat::DoubleElimination => Some(2),
+      TournamentFormat::Swiss => None,
+    }
+  }
+}
+
+// a pairing of a round, a single player is a bye and wins right away
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct TournamentMatch {
+  pub round: u32,
+  pub players: Vec<String>,
+  pub winner: Option<String>,
+  // the running duel of the pairing
+  pub duel_id: Option<usize>,
+}
+
+impl TournamentMatch {
+  pub fn is_bye(&self) -> bool {
+    self.players.len() == 1
+  }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct Tournament {
+  pub tournament_id: usize,
+  pub guild_id: Option<GuildId>,
+  pub channel_id: ChannelId,
+  pub organizer: String,
+  pub format: TournamentFormat,
+  // the user ids of the players, in seed order once the tournament started
+  pub participants: Vec<String>,
+  // 0 while the sign-ups are open
+  pub round: u32,
+  pub matches: Vec<TournamentMatch>,
+  pub winner: Option<String>,
+}
+
+// enough rounds for a single player to be the only one without a loss
+pub fn swiss_rounds(players: usize) -> u32 {
+  (players.max(2) as f64).log2().ceil() as u32
+}
+
+impl Tournament {
+  pub fn is_started(&self) -> bool {
+    self.round > 0
+  }
+
+  pub fn is_finished(&self) -> bool {
+    self.winner.is_some()
+  }
+
+  pub fn wins(&self, player: &String) -> u32 {
+    self
+      .matches
+      .iter()
+      .filter(|current| current.winner.as_ref() == Some(player))
+      .count() as u32
+  }
+
+  pub fn losses(&self, player: &String) -> u32 {
+    self
+      .matches
+      .iter()
+      .filter(|current| {
+        current.players.contains(player)
+          && current.winner.is_some()
+          && current.winner.as_ref() != Some(player)
+      })
+      .count() as u32
+  }
+
+  // sum of the wins of everyone the player met, breaks ties in swiss
+  pub fn buchholz(&self, player: &String) -> u32 {
+    self
+      .matches
+      .iter()
+      .filter(|current| current.players.contains(player))
+      .flat_map(|current| current.players.iter())
+      .filter(|opponent| *opponent != player)
+      .map(|opponent| self.wins(opponent))
+      .sum()
+  }
+
+  fn had_bye(&self, player: &String) -> bool {
+    self
+      .matches
+      .iter()
+      .any(|current| current.is_bye() && current.players.contains(player))
+  }
+
+  fn have_met(&self, a: &String, b: &String) -> bool {
+    self
+      .matches
+      .iter()
+      .any(|current| current.players.contains(a) && current.players.contains(b))
+  }
+
+  pub fn is_round_over(&self) -> bool {
+    self
+      .matches
+      .iter()
+      .filter(|current| current.round == self.round)
+      .all(|current| current.winner.is_some())
+  }
+
+  // the players still in the tournament, in seed order
+  pub fn alive(&self) -> Vec<String> {
+    match self.format.max_losses() {
+      Some(max_losses) => self
+        .participants
+        .iter()
+        .filter(|player| self.losses(player) < max_losses)
+        .cloned()
+        .collect(),
+      None => self.participants.clone(),
+    }
+  }
+
+  // players by wins then buchholz, seeds break the remaining ties
+  pub fn ranking(&self) -> Vec<String> {
+    let mut ranking = self.participants.clone();
+    ranking.sort_by_key(|player| {
+      std::cmp::Reverse((
+        self.wins(player),
+        self.buchholz(player),
+        u32::MAX - self.losses(player),
+      ))
+    });
+    ranking
+  }
+
+  // the best seeded player who hasn't had a bye yet gets it
+  fn take_bye(&self, players: &mut Vec<String>) -> Option<String> {
+    if players.len().is_multiple_of(2) {
+      return None;
+    }
+    let index = players
+      .iter()
+      .position(|player| !self.had_bye(player))
+      .unwrap_or(0);
+    Some(players.remove(index))
+  }
+
+  // the top seed meets the bottom one, the second meets the second to last and so on
+  fn pair_by_seed(&self, mut players: Vec<String>) -> Vec<Vec<String>> {
+    let mut pairings = Vec::new();
+    if let Some(bye) = self.take_bye(&mut players) {
+      pairings.push(vec![bye]);
+    }
+    let half = players.len() / 2;
+    for i in 0..half {
+      pairings.push(vec![
+        players[i].clone(),
+        players[players.len() - 1 - i].clone(),
+      ]);
+    }
+    pairings
+  }
+
+  // neighbours in the ranking meet, avoiding rematches when possible
+  fn pair_swiss(&self) -> Vec<Vec<String>> {
+    let mut players = self.ranking();
+    players.reverse();
+    let mut pairings = Vec::new();
+    if let Some(bye) = self.take_bye(&mut players) {
+      pairings.push(vec![bye]);
+    }
+    players.reverse();
+    while !players.is_empty() {
+      let first = players.remove(0);
+      let index = players
+        .iter()
+        .position(|opponent| !self.have_met(&first, opponent))
+        .unwrap_or(0);
+      let second = players.remove(index);
+      pairings.push(vec![first, second]);
+    }
+    pairings
+  }
+
+  fn get_pairings(&mut self) -> Vec<Vec<String>> {
+    let max_losses = match self.format.max_losses() {
+      Some(max_losses) => max_losses,
+      None => {
+        if self.round >= swiss_rounds(self.participants.len()) {
+          self.winner = self.ranking().first().cloned();
+          return Vec::new();
+        }
+        return self.pair_swiss();
+      }
+    };
+    let alive = self.alive();
+    if alive.len() <= 1 {
+      self.winner = alive.first().cloned();
+      return Vec::new();
+    }
+    // players with the same number of losses are in the same bracket
+    let brackets = (0..max_losses)
+      .map(|losses| {
+        alive
+          .iter()
+          .filter(|player| self.losses(player) == losses)
+          .cloned()
+          .collect::<Vec<_>>()
+      })
+      .filter(|bracket| !bracket.is_empty())
+      .collect::<Vec<_>>();
+    // the last player of every bracket meet in the final
+    if brackets.iter().all(|bracket| bracket.len() == 1) {
+      return vec![alive];
+    }
+    brackets
+      .into_iter()
+      .flat_map(|bracket| self.pair_by_seed(bracket))
+      .collect()
+  }
+
+  /*
+    Start the next round, a bye wins right away. When nobody is left to pair the tournament is
+    over and the winner is set
+  */
+  pub fn next_round(&mut self) {
+    let pairings = self.get_pairings();
+    if self.is_finished() {
+      return;
+    }
+    self.round += 1;
+    for players in pairings {
+      let winner = if players.len() == 1 {
+        players.first().cloned()
+      } else {
+        None
+      };
+      self.matches.push(TournamentMatch {
+        round: self.round,
+        players,
+        winner,
+        duel_id: None,
+      });
+    }
+  }
+
+  /*
+    Store the result of the duel of a pairing. Without a winner the pairing is played again with
+    another duel
+  */
+  pub fn record_result(&mut self, duel_id: usize, winner: Option<String>) -> bool {
+    let current = match self
+      .matches
+      .iter_mut()
+      .find(|current| current.winner.is_none() && current.duel_id == Some(duel_id))
+    {
+      Some(current) => current,
+      None => return false,
+    };
+    current.duel_id = None;
+    current.winner = winner;
+    true
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn players(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+  }
+
+  fn tournament(format: TournamentFormat, size: usize) -> Tournament {
+    Tournament {
+      tournament_id: 0,
+      guild_id: None,
+      channel_id: ChannelId::new(1),
+      organizer: "p1".to_owned(),
+      format,
+      participants: (1..=size).map(|seed| format!("p{seed}")).collect(),
+      round: 0,
+      matches: Vec::new(),
+      winner: None,
+    }
+  }
+
+  // the pairings of the current round, byes included
+  fn round_pairings(tournament: &Tournament) -> Vec<Vec<String>> {
+    tournament
+      .matches
+      .iter()
+      .filter(|current| current.round == tournament.round)
+      .map(|current| current.players.clone())
+      .collect()
+  }
+
+  // every open pairing of the round gets a duel, won by the listed player it holds
+  fn play_round(tournament: &mut Tournament, winners: &[&str]) {
+    let round = tournament.round;
+    for (duel_id, current) in tournament.matches.iter_mut().enumerate() {
+      if current.round == round && current.winner.is_none() {
+        current.duel_id = Some(duel_id);
+      }
+    }
+    for (duel_id, current) in tournament.matches.clone().iter().enumerate() {
+      if current.round != round || current.winner.is_some() {
+        continue;
+      }
+      let winner = winners
+        .iter()
+        .find(|winner| current.players.contains(&winner.to_string()))
+        .map(|winner| winner.to_string());
+      assert!(tournament.record_result(duel_id, winner));
+    }
+    assert!(tournament.is_round_over());
+    tournament.next_round();
+  }
+
+  #[test]
+  fn pair_by_seed_meets_top_and_bottom() {
+    let tournament = tournament(TournamentFormat::SingleElimination, 4);
+    assert_eq!(
+      tournament.pair_by_seed(players(&["p1", "p2", "p3", "p4"])),
+      vec![players(&["p1", "p4"]), players(&["p2", "p3"])]
+    );
+  }
+
+  #[test]
+  fn pair_by_seed_gives_the_bye_to_the_top_seed() {
+    let tournament = tournament(TournamentFormat::SingleElimination, 5);
+    assert_eq!(
+      tournament.pair_by_seed(players(&["p1", "p2", "p3", "p4", "p5"])),
+      vec![
+        players(&["p1"]),
+        players(&["p2", "p5"]),
+        players(&["p3", "p4"])
+      ]
+    );
+  }
+
+  #[test]
+  fn next_round_lets_the_bye_win_right_away() {
+    let mut tournament = tournament(TournamentFormat::SingleElimination, 3);
+    tournament.next_round();
+    assert_eq!(tournament.round, 1);
+    assert_eq!(
+      round_pairings(&tournament),
+      vec![players(&["p1"]), players(&["p2", "p3"])]
+    );
+    assert_eq!(tournament.matches[0].winner, Some("p1".to_owned()));
+    assert!(!tournament.is_round_over());
+
+    play_round(&mut tournament, &["p3"]);
+    assert_eq!(round_pairings(&tournament), vec![players(&["p1", "p3"])]);
+    play_round(&mut tournament, &["p3"]);
+    assert_eq!(tournament.winner, Some("p3".to_owned()));
+  }
+
+  #[test]
+  fn record_result_only_closes_the_running_duel() {
+    let mut tournament = tournament(TournamentFormat::SingleElimination, 2);
+    tournament.next_round();
+    tournament.matches[0].duel_id = Some(7);
+    assert!(!tournament.record_result(8, Some("p1".to_owned())));
+
+    // a duel without a winner leaves the pairing to be played again
+    assert!(tournament.record_result(7, None));
+    assert_eq!(tournament.matches[0].duel_id, None);
+    assert!(!tournament.is_round_over());
+
+    tournament.matches[0].duel_id = Some(9);
+    assert!(tournament.record_result(9, Some("p2".to_owned())));
+    assert!(!tournament.record_result(9, Some("p1".to_owned())));
+    assert_eq!(tournament.matches[0].winner, Some("p2".to_owned()));
+    assert!(tournament.is_round_over());
+  }
+
+  #[test]
+  fn double_elimination_runs_the_losers_bracket() {
+    let mut tournament = tournament(TournamentFormat::DoubleElimination, 4);
+    tournament.next_round();
+    assert_eq!(
+      round_pairings(&tournament),
+      vec![players(&["p1", "p4"]), players(&["p2", "p3"])]
+    );
+
+    // the winners and the losers of the first round meet apart
+    play_round(&mut tournament, &["p1", "p2"]);
+    assert_eq!(
+      round_pairings(&tournament),
+      vec![players(&["p1", "p2"]), players(&["p3", "p4"])]
+    );
+
+    // p4 is out with a second loss, p2 drops to the losers bracket
+    play_round(&mut tournament, &["p1", "p3"]);
+    assert_eq!(tournament.alive(), players(&["p1", "p2", "p3"]));
+    assert_eq!(
+      round_pairings(&tournament),
+      vec![players(&["p1"]), players(&["p2", "p3"])]
+    );
+
+    play_round(&mut tournament, &["p3"]);
+    assert_eq!(round_pairings(&tournament), vec![players(&["p1", "p3"])]);
+
+    // the first loss of p1 in the final calls for one more
+    play_round(&mut tournament, &["p3"]);
+    assert_eq!(tournament.winner, None);
+    assert_eq!(round_pairings(&tournament), vec![players(&["p1", "p3"])]);
+
+    play_round(&mut tournament, &["p1"]);
+    assert_eq!(tournament.winner, Some("p1".to_owned()));
+    assert_eq!(tournament.alive(), players(&["p1"]));
+  }
+
+  #[test]
+  fn pair_swiss_pairs_neighbours_without_rematches() {
+    let mut tournament = tournament(TournamentFormat::Swiss, 4);
+    tournament.next_round();
+    assert_eq!(
+      round_pairings(&tournament),
+      vec![players(&["p1", "p2"]), players(&["p3", "p4"])]
+    );
+
+    play_round(&mut tournament, &["p1", "p3"]);
+    assert_eq!(
+      round_pairings(&tournament),
+      vec![players(&["p1", "p3"]), players(&["p2", "p4"])]
+    );
+
+    // the ranking leader wins once every swiss round was played
+    play_round(&mut tournament, &["p1", "p4"]);
+    assert_eq!(tournament.round, swiss_rounds(4));
+    assert_eq!(tournament.winner, Some("p1".to_owned()));
+  }
+
+  #[test]
+  fn pair_swiss_gives_the_bye_to_the_last_ranked() {
+    let mut tournament = tournament(TournamentFormat::Swiss, 3);
+    tournament.next_round();
+    assert_eq!(
+      round_pairings(&tournament),
+      vec![players(&["p3"]), players(&["p1", "p2"])]
+    );
+
+    // p3 already had a bye, the next lowest ranked without one gets it
+    play_round(&mut tournament, &["p1"]);
+    assert_eq!(
+      round_pairings(&tournament),
+      vec![players(&["p2"]), players(&["p1", "p3"])]
+    );
+  }
+}
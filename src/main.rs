@@ -14,16 +14,29 @@ use serenity::model::event::ResumedEvent;
 use serenity::model::gateway::Ready;
 use serenity::prelude::*;
 
+use crate::commands::config::*;
+use crate::commands::contests::*;
 use crate::commands::duel::*;
 use crate::commands::giveme::*;
 use crate::commands::handle::*;
 use crate::commands::help::*;
 use crate::commands::latency::*;
 use crate::commands::lockout::*;
+use crate::commands::mashup::*;
+use crate::commands::match_control::*;
 use crate::commands::math::*;
 use crate::commands::message::*;
 use crate::commands::ping::*;
+use crate::commands::potd::*;
+use crate::commands::profile::*;
+use crate::commands::queue::*;
 use crate::commands::rating::*;
+use crate::commands::rating_changes::*;
+use crate::commands::standings::*;
+use crate::commands::tournament::*;
+use crate::commands::track::*;
+use crate::commands::virtual_contest::*;
+use crate::commands::streak::*;
 
 use crate::core::data::*;
 
@@ -40,6 +53,15 @@ impl EventHandler for Handler {
     info!("{} is connected!", ready.user.name);
     duel_interactor(&ctx).await;
     lockout_interactor(&ctx).await;
+    virtual_interactor(&ctx).await;
+    mashup_interactor(&ctx).await;
+    potd_interactor(&ctx).await;
+    streak_interactor(&ctx).await;
+    contest_reminder_interactor(&ctx).await;
+    rating_change_interactor(&ctx).await;
+    tracker_interactor(&ctx).await;
+    tournament_interactor(&ctx).await;
+    queue_interactor(&ctx).await;
   }
 
   // For instrument to work, all parameters must implement Debug.
@@ -74,7 +96,9 @@ async fn before(ctx: &Context, msg: &Message, command_name: &str) -> bool {
 
 #[group]
 #[commands(
-  handle, ping, message, multiply, rating, giveme, gotit, skip, latency, duel, lockout
+  handle, ping, message, multiply, rating, giveme, gotit, skip, latency, duel, lockout, config,
+  potd, streak, profile, contests, rating_changes, standings, track, virtual_contest,
+  mashup, tournament, queue, match_command
 )]
 struct General;
 
@@ -107,7 +131,8 @@ async fn main() {
     .help(&MY_HELP);
   framework.configure(Configuration::new().owners(owners).prefix("~"));
 
-  let intents = GatewayIntents::GUILD_MESSAGES
+  let intents = GatewayIntents::GUILDS
+    | GatewayIntents::GUILD_MESSAGES
     | GatewayIntents::DIRECT_MESSAGES
     | GatewayIntents::MESSAGE_CONTENT;
   let mut client = Client::builder(&token, intents)
@@ -5,11 +5,19 @@ use serenity::model::Timestamp;
 use serenity::prelude::*;
 
 use crate::Problem;
-use std::time::{Duration, SystemTime};
+use std::time::Duration;
 
 use crate::commands::giveme::*;
 use crate::commands::lockout::*;
 use crate::core::data::*;
+use crate::core::scoring::*;
+
+const EMBED_FIELD_LIMIT: usize = 1024;
+// room for the `... and 12 more` note of a cut field
+const CUT_NOTE_ROOM: usize = 32;
+// the characters the standings and the problems of a scoreboard take at most, out of 6000
+const STANDINGS_BUDGET: usize = 3500;
+const PROBLEMS_BUDGET: usize = 2000;
 
 #[macro_export]
 macro_rules! error_response {
@@ -61,9 +69,53 @@ pub async fn edit_to_failed_status(ctx: &Context, mut message: Message) {
   let _ = message.edit(&ctx, builder).await;
 }
 
-fn get_standing_string(lockout: &Duel) -> String {
+// a line per team with its points, followed by what each of its players claimed
+// a line per team followed by its members, kept together in the same field
+fn get_team_standing_lines(lockout: &Duel) -> Vec<String> {
+  let teams = lockout.teams.clone().unwrap_or_default();
+  let score = lockout.score_distribution.clone().unwrap_or_default();
+  let mut standings: Vec<String> = Vec::new();
+  let mut ranking = 1;
+  let leaderboard = get_team_leaderboard(lockout);
+  for (pos, (team, points)) in leaderboard.iter().enumerate() {
+    if pos > 0 && *points != leaderboard[pos - 1].1 {
+      ranking = pos + 1;
+    }
+    let pos_string: String = match ranking {
+      1 => ":first_place:".to_owned(),
+      2 => ":second_place:".to_owned(),
+      3 => ":third_place:".to_owned(),
+      _ => ranking.to_string(),
+    };
+    let mut current = format!("{pos_string} Team {} **{points}** points", team + 1);
+    for (i, player) in lockout.players.iter().enumerate() {
+      if teams.get(i) != Some(team) {
+        continue;
+      }
+      current += format!(
+        "\n- {user_link} {points} points",
+        user_link = create_href(
+          format!(
+            "https://codeforces.com/profile/{handle}",
+            handle = player.handle
+          ),
+          &player.handle
+        ),
+        points = score.get(i).copied().unwrap_or_default()
+      )
+      .as_str();
+    }
+    standings.push(current);
+  }
+  standings
+}
+
+fn get_standing_lines(lockout: &Duel) -> Vec<String> {
+  if lockout.teams.is_some() {
+    return get_team_standing_lines(lockout);
+  }
   let indices: Vec<usize> = get_leaderboard_indices(&lockout);
-  let mut standings: String = String::new();
+  let mut standings: Vec<String> = Vec::new();
   let score = lockout.score_distribution.clone().unwrap();
   let mut ranking = 1;
   let players = lockout.players.clone();
@@ -79,7 +131,7 @@ fn get_standing_string(lockout: &Duel) -> String {
       _ => ranking.clone().to_string(),
     };
     let current = format!(
-      "{position} {user_link} {points} points\n",
+      "{position} {user_link} {points} points",
       position = pos_string,
       user_link = create_href(
         format!(
@@ -90,7 +142,7 @@ fn get_standing_string(lockout: &Duel) -> String {
       ),
       points = format!("**{score}**", score = score[*i])
     );
-    standings += current.as_str();
+    standings.push(current);
   }
   standings
 }
@@ -99,9 +151,23 @@ fn get_points_string(lockout: &Duel) -> String {
   let mut points: String = String::new();
   let problems_point: Vec<u32> = lockout.problems_point.clone().unwrap();
 
-  for point in problems_point.iter() {
+  let claimed_points = lockout.claimed_points.clone().unwrap_or_default();
+  let claimed_at = lockout.claimed_at.clone().unwrap_or_default();
+  let decaying = lockout.scoring == Some(ScoringStyle::Codeforces);
+  for (i, point) in problems_point.iter().enumerate() {
     if *point == 0 {
       points += "Locked";
+      match (claimed_points.get(i), claimed_at.get(i)) {
+        (Some(claimed), Some(solved_at)) if decaying => {
+          points += format!(" ({claimed} at {})", format_match_time(*solved_at)).as_str();
+        }
+        (_, Some(solved_at)) => {
+          points += format!(" (at {})", format_match_time(*solved_at)).as_str();
+        }
+        _ => {}
+      }
+    } else if decaying {
+      points += format!("{} / {point}", get_decayed_point(lockout, *point)).as_str();
     } else {
       points += point.to_string().as_str();
     }
@@ -110,12 +176,22 @@ fn get_points_string(lockout: &Duel) -> String {
   points
 }
 
+// what an open problem is worth if it gets claimed right now without rejections, pauses aside
+fn get_decayed_point(lockout: &Duel, point: u32) -> u32 {
+  let elapsed = lockout.begin_time.elapsed().unwrap_or_default().as_secs();
+  let attempt = ProblemAttempt {
+    solved_at: Some(lockout.get_running_seconds(elapsed)),
+    wrong_attempts: 0,
+  };
+  decayed_points(point as f64, &attempt, 0f64).map_or(point, |points| points as u32)
+}
+
 fn get_ratings_string(lockout: &Duel) -> String {
   let mut ratings: String = String::new();
   let problems = lockout.problems.clone();
 
   for problem in problems.iter() {
-    ratings += (problem.rating.unwrap()).to_string().as_str();
+    ratings += problem.difficulty_string().unwrap_or_default().as_str();
     ratings += "\n";
   }
   ratings
@@ -172,17 +248,10 @@ fn get_gym_problems_string(vec_problems: &Vec<Problem>) -> String {
 }
 
 fn get_time_left_string(lockout: &Duel) -> String {
-  let time_left: String;
-  let time_so_far = SystemTime::now()
-    .duration_since(lockout.begin_time)
-    .unwrap();
-  if time_so_far > lockout.match_duration.unwrap() || is_lockout_complete(&lockout) {
-    time_left = format!("Ended");
-  } else {
-    let (_, minutes, hours) = convert_to_hms(&(lockout.match_duration.unwrap() - time_so_far));
-    time_left = format!("Time left: {hours} hour(s) and {minutes} minute(s)");
+  if is_lockout_complete(lockout) {
+    return "Ended".to_owned();
   }
-  time_left
+  get_match_time_left_string(lockout)
 }
 
 pub fn create_problems_embed(vec_problem: &Vec<Problem>, elapsed_time: &Duration) -> CreateEmbed {
@@ -201,39 +270,262 @@ pub fn create_problems_embed(vec_problem: &Vec<Problem>, elapsed_time: &Duration
 }
 
 pub fn create_lockout_status_embed(lockout: &Duel, show_problem_set: bool) -> CreateEmbed {
-  let standings: String = get_standing_string(&lockout);
+  if is_scored_by_attempts(lockout) {
+    return create_scored_lockout_embed(lockout, show_problem_set);
+  }
+  let standings: Vec<String> = get_standing_lines(lockout);
   let time_left = get_time_left_string(&lockout);
   let footer = CreateEmbedFooter::new(&time_left);
+  let title = match lockout.wrong_penalty {
+    Some(penalty) if lockout.scoring == Some(ScoringStyle::Codeforces) => {
+      format!("Lockout match (decaying points, -{penalty} per rejected submission):")
+    }
+    _ => "Lockout match:".to_owned(),
+  };
   let embed;
   if show_problem_set {
     let points: String = get_points_string(&lockout);
     let ratings: String = get_ratings_string(&lockout);
     let problems: String = get_problems_string(&lockout);
 
-    embed = CreateEmbed::new()
-      .title("Lockout match:")
-      .field("Standings", standings, false)
-      .field("Points", points, true)
-      .field("Problems", problems, true)
-      .field("Rating", ratings, true)
-      .colour(if &time_left == "Ended" {
-        Colour::GOLD
-      } else {
-        Colour::TEAL
-      })
-      .footer(footer);
+    embed = add_line_fields(
+      CreateEmbed::new().title(title),
+      "Standings",
+      &standings,
+      STANDINGS_BUDGET,
+    )
+    .field("Points", points, true)
+    .field("Problems", problems, true)
+    .field("Rating", ratings, true)
+    .colour(if &time_left == "Ended" {
+      Colour::GOLD
+    } else {
+      Colour::TEAL
+    })
+    .footer(footer);
   } else {
-    embed = CreateEmbed::new()
-      .title("Lockout match:")
-      .field("Standings", standings, false)
-      .colour(if &time_left == "Ended" {
-        Colour::GOLD
-      } else {
-        Colour::TEAL
-      })
-      .footer(footer);
+    embed = add_line_fields(
+      CreateEmbed::new().title(title),
+      "Standings",
+      &standings,
+      STANDINGS_BUDGET,
+    )
+    .colour(if &time_left == "Ended" {
+      Colour::GOLD
+    } else {
+      Colour::TEAL
+    })
+    .footer(footer);
+  }
+  embed
+}
+
+// every player can solve every problem, the standings come from the attempts
+fn create_scored_lockout_embed(lockout: &Duel, show_problem_set: bool) -> CreateEmbed {
+  let style = lockout.scoring.unwrap_or(ScoringStyle::Icpc);
+  let max_points = lockout
+    .problems_point
+    .clone()
+    .unwrap_or_default()
+    .iter()
+    .map(|point| *point as f64)
+    .collect::<Vec<_>>();
+  let attempts = lockout.attempts.clone().unwrap_or_else(|| {
+    vec![vec![ProblemAttempt::default(); lockout.problems.len()]; lockout.players.len()]
+  });
+  let rows = rank_attempts(style, &attempts, &max_points);
+  let time_left = get_time_left_string(lockout);
+  let mut embed = add_line_fields(
+    CreateEmbed::new().title(format!("Lockout match ({} scoring):", style.name())),
+    "Standings",
+    &get_scoreboard_lines(lockout, &rows),
+    STANDINGS_BUDGET,
+  );
+  if show_problem_set {
+    embed = embed
+      .field("Problems", get_problems_string(lockout), true)
+      .field("Rating", get_ratings_string(lockout), true);
   }
   embed
+    .colour(if time_left == "Ended" {
+      Colour::GOLD
+    } else {
+      Colour::TEAL
+    })
+    .footer(CreateEmbedFooter::new(&time_left))
+}
+
+pub fn problem_url(problem: &Problem) -> String {
+  let contest_id = problem.contestId.unwrap_or_default();
+  // gym contests have ids from 100000
+  let section = if contest_id >= 100000 {
+    "gym"
+  } else {
+    "contest"
+  };
+  format!(
+    "https://codeforces.com/{section}/{contest_id}/problem/{index}",
+    index = problem.index
+  )
+}
+
+pub fn get_match_time_left_string(duel: &Duel) -> String {
+  let time_left = duel.get_time_left();
+  if time_left.is_zero() {
+    return "Ended".to_owned();
+  }
+  let (_, minutes, hours) = convert_to_hms(&time_left);
+  let paused = if duel.is_paused() { " (paused)" } else { "" };
+  format!("Time left: {hours} hour(s) and {minutes} minute(s){paused}")
+}
+
+// seconds after the start of a match as hours and minutes
+pub fn format_match_time(seconds: u64) -> String {
+  let (_, minutes, hours) = convert_to_hms(&Duration::from_secs(seconds));
+  format!("{hours:0>2}:{minutes:0>2}")
+}
+
+pub fn get_attempt_cell(
+  index: &String,
+  attempt: &ProblemAttempt,
+  style: ScoringStyle,
+  max_points: f64,
+) -> Option<String> {
+  match attempt.solved_at {
+    Some(solved_at) => {
+      let result = match style {
+        ScoringStyle::Icpc if attempt.wrong_attempts == 0 => "+".to_owned(),
+        ScoringStyle::Icpc => format!("+{}", attempt.wrong_attempts),
+        ScoringStyle::Codeforces => cf_points(max_points, attempt)
+          .unwrap_or_default()
+          .to_string(),
+      };
+      Some(format!(
+        "{index} {result} ({})",
+        format_match_time(solved_at)
+      ))
+    }
+    None if attempt.wrong_attempts > 0 => Some(format!("{index} -{}", attempt.wrong_attempts)),
+    None => None,
+  }
+}
+
+// one line per player with the score, then a cell for every problem they tried
+// a line per player of the scoreboard, with their rank, score and what they did on every problem
+fn get_scoreboard_lines(duel: &Duel, rows: &[ScoreboardRow]) -> Vec<String> {
+  let style = duel.scoring.unwrap_or(ScoringStyle::Icpc);
+  let max_points = duel.problems_point.clone().unwrap_or_default();
+  rows
+    .iter()
+    .map(|row| {
+      let player = &duel.players[row.player];
+      let score = match style {
+        ScoringStyle::Icpc => format!(
+          "**{solved}** solved, {penalty} penalty",
+          solved = row.score.solved,
+          penalty = row.score.penalty
+        ),
+        ScoringStyle::Codeforces => format!("**{}** points", row.score.points),
+      };
+      let cells = duel
+        .problems
+        .iter()
+        .zip(row.attempts.iter())
+        .enumerate()
+        .filter_map(|(i, (problem, attempt))| {
+          let max_points = max_points.get(i).copied().unwrap_or_default() as f64;
+          get_attempt_cell(&problem.index, attempt, style, max_points)
+        })
+        .collect::<Vec<_>>();
+      format!(
+        "`{rank}.` {user_link} {score}\n{cells}",
+        rank = row.rank,
+        user_link = create_href(
+          format!(
+            "https://codeforces.com/profile/{handle}",
+            handle = player.handle
+          ),
+          &player.handle
+        ),
+        cells = if cells.is_empty() {
+          "No attempt yet".to_owned()
+        } else {
+          cells.join(" | ")
+        }
+      )
+    })
+    .collect::<Vec<_>>()
+}
+
+/*
+  Put the lines in as many fields named `name` as needed, a field holds at most 1024 characters.
+  The lines past `budget` characters are left out, an embed holds at most 6000 characters
+*/
+pub fn add_line_fields(
+  mut embed: CreateEmbed,
+  name: &str,
+  lines: &[String],
+  budget: usize,
+) -> CreateEmbed {
+  let mut fields = vec![String::new()];
+  let mut used = 0;
+  for (i, line) in lines.iter().enumerate() {
+    // keep room to tell how many lines were left out
+    if used + line.len() + CUT_NOTE_ROOM > budget {
+      let field = fields.last_mut().unwrap();
+      *field += format!("\n... and {} more", lines.len() - i).as_str();
+      break;
+    }
+    if fields.last().unwrap().len() + line.len() + CUT_NOTE_ROOM > EMBED_FIELD_LIMIT {
+      fields.push(String::new());
+    }
+    let field = fields.last_mut().unwrap();
+    if !field.is_empty() {
+      field.push('\n');
+    }
+    *field += line.as_str();
+    used += line.len() + 1;
+  }
+  for (i, value) in fields.into_iter().enumerate() {
+    let name = if i == 0 {
+      name.to_owned()
+    } else {
+      format!("{name} (continued)")
+    };
+    embed = embed.field(name, value, false);
+  }
+  embed
+}
+
+pub fn create_scoreboard_embed(duel: &Duel, rows: &[ScoreboardRow]) -> CreateEmbed {
+  let time_left = get_match_time_left_string(duel);
+  let problems = duel
+    .problems
+    .iter()
+    .map(|problem| {
+      create_href(
+        problem_url(problem),
+        &format!("{}. {}", problem.index, problem.name),
+      )
+    })
+    .collect::<Vec<_>>();
+  let mut standings = get_scoreboard_lines(duel, rows);
+  if standings.is_empty() {
+    standings.push("Nobody is on the scoreboard yet".to_owned());
+  }
+  let title = match &duel.contest {
+    Some(contest) => format!("Virtual contest: {}", contest.name),
+    None => "Scoreboard".to_owned(),
+  };
+  let embed = CreateEmbed::new().title(title);
+  let embed = add_line_fields(embed, "Standings", &standings, STANDINGS_BUDGET);
+  add_line_fields(embed, "Problems", &problems, PROBLEMS_BUDGET)
+    .colour(if time_left == "Ended" {
+      Colour::GOLD
+    } else {
+      Colour::TEAL
+    })
+    .footer(CreateEmbedFooter::new(&time_left))
 }
 
 pub fn create_lockout_status(lockout: &Duel, show_problem_set: bool) -> CreateMessage {
@@ -278,7 +570,14 @@ pub fn create_problem_message(
   if problem.contestId.is_none() {
     return None;
   }
-  let contest_id = problem.contestId.unwrap();
+  let embed = create_problem_embed(problem, show_rating);
+  let builder = CreateMessage::new().content(message).embed(embed);
+
+  Some(builder)
+}
+
+pub fn create_problem_embed(problem: &Problem, show_rating: bool) -> CreateEmbed {
+  let contest_id = problem.contestId.unwrap_or_default();
   let problem_url = format!(
     "https://codeforces.com/contest/{contestid}/problem/{index}",
     index = problem.index,
@@ -293,13 +592,10 @@ pub fn create_problem_message(
     .title(title)
     .colour(Colour::GOLD)
     .url(problem_url);
-  if let Some(rating) = problem.rating {
+  if let Some(rating) = problem.difficulty_string() {
     if show_rating == true {
-      embed = embed.field("Rating", rating.to_string(), false);
+      embed = embed.field("Rating", rating, false);
     }
   }
-  embed = embed.timestamp(Timestamp::now());
-  let builder = CreateMessage::new().content(message).embed(embed);
-
-  Some(builder)
+  embed.timestamp(Timestamp::now())
 }
@@ -1 +1,2 @@
 pub mod message_creator;
+pub mod time;
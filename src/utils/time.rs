@@ -0,0 +1,93 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const SECONDS_PER_DAY: i64 = 60 * 60 * 24;
+
+pub fn unix_now() -> u64 {
+  unix_seconds(SystemTime::now())
+}
+
+pub fn unix_seconds(time: SystemTime) -> u64 {
+  time
+    .duration_since(UNIX_EPOCH)
+    .map(|duration| duration.as_secs())
+    .unwrap_or(0)
+}
+
+/*
+  Convert a number of days since 1970-01-01 into a (year, month, day) date.
+  http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+*/
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+  let z = days + 719468;
+  let era = z.div_euclid(146097);
+  let doe = z.rem_euclid(146097);
+  let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+  let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+  let mp = (5 * doy + 2) / 153;
+  let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+  let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+  let year = yoe + era * 400 + (month <= 2) as i64;
+  (year, month, day)
+}
+
+pub fn year_of(unix_seconds: u64) -> i64 {
+  civil_from_days(unix_seconds as i64 / SECONDS_PER_DAY).0
+}
+
+// the day number of `unix_seconds` for a timezone `utc_offset` minutes away from UTC
+pub fn local_day(unix_seconds: u64, utc_offset: i32) -> i64 {
+  (unix_seconds as i64 + utc_offset as i64 * 60).div_euclid(SECONDS_PER_DAY)
+}
+
+// minutes after the local midnight
+pub fn local_minute_of_day(unix_seconds: u64, utc_offset: i32) -> u32 {
+  ((unix_seconds as i64 + utc_offset as i64 * 60).rem_euclid(SECONDS_PER_DAY) / 60) as u32
+}
+
+pub fn format_utc_offset(utc_offset: i32) -> String {
+  let sign = if utc_offset < 0 { '-' } else { '+' };
+  format!(
+    "UTC{sign}{:0>2}:{:0>2}",
+    utc_offset.abs() / 60,
+    utc_offset.abs() % 60
+  )
+}
+
+// `+7`, `-05:30`, `UTC+9` or `0` into minutes away from UTC
+pub fn parse_utc_offset(offset: &str) -> Option<i32> {
+  let offset = offset.trim().to_uppercase();
+  if offset.is_empty() {
+    return None;
+  }
+  let offset = offset.strip_prefix("UTC").unwrap_or(&offset);
+  if offset.is_empty() || offset == "0" {
+    return Some(0);
+  }
+  let (sign, rest) = match offset.chars().next()? {
+    '+' => (1, &offset[1..]),
+    '-' => (-1, &offset[1..]),
+    _ => (1, offset),
+  };
+  let (hours, minutes) = match rest.split_once(':') {
+    Some((hours, minutes)) => (hours.parse::<i32>().ok()?, minutes.parse::<i32>().ok()?),
+    None => (rest.parse::<i32>().ok()?, 0),
+  };
+  if minutes >= 60 {
+    return None;
+  }
+  let total = sign * (hours * 60 + minutes);
+  if !(-12 * 60..=14 * 60).contains(&total) {
+    return None;
+  }
+  Some(total)
+}
+
+// `2h 15m` style length of a contest
+pub fn format_duration(seconds: u64) -> String {
+  let (hours, minutes) = (seconds / 3600, seconds % 3600 / 60);
+  match (hours, minutes) {
+    (0, minutes) => format!("{minutes}m"),
+    (hours, 0) => format!("{hours}h"),
+    (hours, minutes) => format!("{hours}h {minutes}m"),
+  }
+}